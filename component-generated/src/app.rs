@@ -1,113 +1,649 @@
-use color_eyre::Result;
-use crossterm::event::KeyEvent;
-use ratatui::prelude::Rect;
+use std::{
+    collections::HashSet,
+    io::Stdout,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use color_eyre::{eyre::eyre, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Position, Rect, Size},
+    widgets::Clear,
+    Frame, Terminal, Viewport,
+};
 use serde::{Deserialize, Serialize};
-use tokio::sync::mpsc;
-use tracing::{debug, info};
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    time,
+};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    action::Action,
-    components::{fps::FpsCounter, home::Home, Component},
+    action::{Action, Severity},
+    capabilities::Capabilities,
+    components::{
+        self, about::About, fps::FpsCounter, help::Help, home::Home, notifications::Notifications,
+        Component,
+    },
     config::Config,
-    tui::{Event, Tui},
+    editor,
+    errors::ErrorDetails,
+    focus::FocusRing,
+    gesture::{Gesture, GestureRecognizer},
+    jobs::Jobs,
+    layout::{LayoutNode, LayoutRegistry},
+    overlay::OverlayLayer,
+    router::Router,
+    session::{Recorder, Replay},
+    signals,
+    tui::{Event, EventSource, Rates, Tui},
 };
 
+#[cfg(test)]
+pub mod harness;
+
+/// How long the components get to tear down when the app quits.
+const TEARDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
 pub struct App {
     config: Config,
-    tick_rate: f64,
-    frame_rate: f64,
+    capabilities: Capabilities,
+    rates: Rates,
+    power_policy: PowerPolicy,
+    terminal_focused: bool,
     components: Vec<Box<dyn Component>>,
+    focus: FocusRing,
+    layout: LayoutRegistry,
+    overlays: Vec<OverlayLayer>,
     should_quit: bool,
     should_suspend: bool,
-    mode: Mode,
+    /// The component id and text of an edit that is waiting for the user's editor.
+    edit: Option<(String, String)>,
+    /// The editor for [`Action::Edit`], instead of the user's editor.
+    editor: Option<String>,
+    jobs: Jobs,
+    /// The text that was copied last, which is pasted by [`Action::Paste`].
+    register: String,
+    /// Text that is waiting to be sent to the terminal's clipboard.
+    copy: Option<String>,
+    /// Whether something changed since the last frame was drawn.
+    dirty: bool,
+    router: Router,
     last_tick_key_events: Vec<KeyEvent>,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+    /// Whether a replay is drawn at the sizes that the session was recorded at, instead of
+    /// following the size of the terminal.
+    replay_sized: bool,
+    viewport: Viewport,
+    keyboard_enhancement: bool,
+    paste: bool,
+    mouse: bool,
+    click_to_focus: bool,
+    /// Where each component was drawn in the last frame, topmost last.
+    hit_areas: Vec<HitArea>,
+    /// The component that the held mouse button was pressed on.
+    mouse_capture: Option<String>,
+    /// The topmost component under the pointer.
+    hovered: Option<String>,
+    gestures: GestureRecognizer,
+    recorder: Option<Recorder>,
     action_tx: mpsc::UnboundedSender<Action>,
     action_rx: mpsc::UnboundedReceiver<Action>,
+    /// Every action that was dispatched, for the test harness.
+    #[cfg(test)]
+    dispatched: Vec<Action>,
 }
 
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Mode {
     #[default]
     Home,
+    About,
+}
+
+/// The area that a component was drawn in, used to find the component under the mouse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HitArea {
+    id: String,
+    area: Rect,
+    focusable: bool,
+    /// 0 for the base UI, or the number of the overlay that the component is in.
+    layer: usize,
+}
+
+/// What the app does with ticks and frames while the terminal is unfocused.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum PowerPolicy {
+    /// Keep ticking and drawing at the configured rates.
+    AlwaysOn,
+    /// Tick and draw at most once per second.
+    #[default]
+    Throttle,
+    /// Stop ticking and drawing until the terminal is focused again.
+    Suspend,
 }
 
 impl App {
     pub fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
         let (action_tx, action_rx) = mpsc::unbounded_channel();
         Ok(Self {
-            tick_rate,
-            frame_rate,
-            components: vec![Box::new(Home::new()), Box::new(FpsCounter::default())],
+            rates: Rates {
+                tick_rate,
+                frame_rate,
+                ..Rates::default()
+            },
+            capabilities: Capabilities::default(),
+            power_policy: PowerPolicy::default(),
+            terminal_focused: true,
+            components: vec![
+                Box::new(Home::new()),
+                Box::new(About::new()),
+                Box::new(Notifications::new()),
+            ],
+            focus: FocusRing::default(),
+            layout: LayoutRegistry::new(LayoutNode::vertical([
+                (Constraint::Length(1), LayoutNode::slot("header")),
+                (Constraint::Fill(1), LayoutNode::slot("body")),
+            ]))
+            .assign("FpsCounter", "header")
+            .assign("Home", "body")
+            .assign("About", "body"),
+            overlays: Vec::new(),
             should_quit: false,
             should_suspend: false,
+            edit: None,
+            editor: None,
+            jobs: Jobs::new(action_tx.clone()),
+            register: String::new(),
+            copy: None,
+            dirty: true,
             config: Config::new()?,
-            mode: Mode::Home,
+            router: Router::new(Mode::Home)
+                .screen(Mode::Home, ["Home"])
+                .screen(Mode::About, ["About"]),
             last_tick_key_events: Vec::new(),
+            record: None,
+            replay: None,
+            replay_sized: false,
+            viewport: Viewport::Fullscreen,
+            keyboard_enhancement: false,
+            paste: false,
+            mouse: false,
+            click_to_focus: true,
+            hit_areas: Vec::new(),
+            mouse_capture: None,
+            hovered: None,
+            gestures: GestureRecognizer::default(),
+            recorder: None,
             action_tx,
             action_rx,
+            #[cfg(test)]
+            dispatched: Vec::new(),
         })
     }
 
+    /// Record every incoming event and dispatched action to a file.
+    pub fn record(mut self, path: Option<PathBuf>) -> Self {
+        self.record = path;
+        self
+    }
+
+    /// Replay the events of a recorded session instead of reading them from the terminal.
+    pub fn replay(mut self, path: Option<PathBuf>) -> Self {
+        self.replay = path;
+        self
+    }
+
+    /// Show the tick and frame rates in the header. This draws every frame, so it is only meant for
+    /// debugging.
+    pub fn show_fps(mut self, show_fps: bool) -> Self {
+        if show_fps {
+            self.components.push(Box::new(FpsCounter::new()));
+        }
+        self
+    }
+
+    /// Choose how to save power while the terminal is unfocused.
+    pub fn power_policy(mut self, power_policy: PowerPolicy) -> Self {
+        self.power_policy = power_policy;
+        self
+    }
+
+    /// Draw the app in the given viewport, e.g. inline below the shell prompt.
+    pub fn viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    /// Ask the terminal for the kitty keyboard protocol, which reports key releases and tells
+    /// apart keys such as `Ctrl-i` and `Tab`.
+    pub fn keyboard_enhancement(mut self, keyboard_enhancement: bool) -> Self {
+        self.keyboard_enhancement = keyboard_enhancement;
+        self
+    }
+
+    /// Enable bracketed paste, so that pasted text arrives as a single event. This can also be
+    /// enabled with the `paste` setting in the config.
+    pub fn paste(mut self, paste: bool) -> Self {
+        self.paste = paste;
+        self
+    }
+
+    /// Enable mouse support. Mouse events are delivered to the component under the pointer.
+    pub fn mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+
+    /// Whether clicking a focusable component gives it focus. This is enabled by default.
+    #[allow(dead_code)] // Remove this once you start using the code
+    pub fn click_to_focus(mut self, click_to_focus: bool) -> Self {
+        self.click_to_focus = click_to_focus;
+        self
+    }
+
+    /// Edit text with the given editor command, e.g. `code --wait`, instead of `$VISUAL` or
+    /// `$EDITOR`.
+    #[allow(dead_code)] // Remove this once you start using the code
+    pub fn editor(mut self, editor: impl Into<String>) -> Self {
+        self.editor = Some(editor.into());
+        self
+    }
+
     pub async fn run(&mut self) -> Result<()> {
-        let mut tui = Tui::new()?
-            // .mouse(true) // uncomment this line to enable mouse support
-            .tick_rate(self.tick_rate)
-            .frame_rate(self.frame_rate);
+        let Some(path) = self.replay.take() else {
+            let tui = Tui::new()?.viewport(self.viewport.clone())?;
+            return self.run_in_terminal(tui).await;
+        };
+        let replay = Replay::open(&path)?;
+        let (width, height) = crossterm::terminal::size()?;
+        replay.check_fits(Size::new(width, height))?;
+        let viewport = match replay.size() {
+            Some(size) => {
+                self.replay_sized = true;
+                Viewport::Fixed(Rect::from((Position::ORIGIN, size)))
+            }
+            None => {
+                warn!("The session was recorded without its size, replaying at the current size");
+                self.viewport.clone()
+            }
+        };
+        let tui = Tui::with_source(replay)?.viewport(viewport)?;
+        self.run_in_terminal(tui).await
+    }
+
+    async fn run_in_terminal<S: EventSource>(
+        &mut self,
+        tui: Tui<CrosstermBackend<Stdout>, S>,
+    ) -> Result<()> {
+        let tui = tui
+            .keyboard_enhancement(self.keyboard_enhancement)
+            .paste(self.paste || self.config.config.paste)
+            .mouse(self.mouse);
+        // quit, reload and resume on signals instead of being killed with the terminal in raw mode
+        let _signals = signals::listen(tui.event_tx.clone())?;
+        self.run_with(tui).await
+    }
+
+    /// Run the app on any [`Tui`], e.g. one built with another backend or event source.
+    pub async fn run_with<B: Backend, S: EventSource>(&mut self, mut tui: Tui<B, S>) -> Result<()> {
+        self.start(&mut tui)?;
+        while !self.step(&mut tui).await? {}
+        self.shut_down(&mut tui).await
+    }
+
+    /// Enter the terminal and initialize the components.
+    fn start<B: Backend, S: EventSource>(&mut self, tui: &mut Tui<B, S>) -> Result<()> {
+        if let Some(path) = &self.record {
+            self.recorder = Some(Recorder::create(path)?);
+        }
+        tui.set_rates(self.loop_rates()?);
         tui.enter()?;
+        if let Some(recorder) = &mut self.recorder {
+            // so that the session can be replayed at the same size
+            let size = tui.size()?;
+            recorder.record_event(&Event::Resize(size.width, size.height))?;
+        }
+        self.capabilities = tui.capabilities();
+        info!("Terminal capabilities: {}", self.capabilities);
+        self.init_components(tui.get_frame().area())
+    }
 
+    /// Wait for the next event or action, e.g. from a job, and handle it along with every action
+    /// that it causes. Returns whether the app should quit.
+    async fn step<B: Backend, S: EventSource>(&mut self, tui: &mut Tui<B, S>) -> Result<bool> {
+        tokio::select! {
+            // the actions that are waiting are handled below either way
+            biased;
+            Some(event) = tui.next_event() => self.handle_event(event)?,
+            Some(action) = self.action_rx.recv() => self.handle_action(&mut tui.terminal, action)?,
+        }
+        self.handle_actions(&mut tui.terminal)?;
+        tui.set_rates(self.loop_rates()?);
+        if let Some(text) = self.copy.take() {
+            if !tui.copy(&text)? {
+                debug!("The terminal has no clipboard, only copied to the register");
+            }
+        }
+        if let Some((id, text)) = self.edit.take() {
+            let editor = self.editor.clone().unwrap_or_else(editor::command);
+            let action = match tui.release(editor::edit_with(&editor, &text)).await? {
+                Ok(text) => Action::Edited { id, text },
+                Err(err) => Action::Error(ErrorDetails::from_report(&err).component(id)),
+            };
+            self.action_tx.send(action)?;
+        }
+        if self.should_suspend {
+            tui.suspend().await?;
+            self.action_tx.send(Action::Resume)?;
+            self.action_tx.send(Action::ClearScreen)?;
+            tui.resume()?;
+            return Ok(false);
+        }
+        Ok(self.should_quit)
+    }
+
+    /// Stop reading events, let the components clean up, then restore the terminal.
+    async fn shut_down<B: Backend, S: EventSource>(&mut self, tui: &mut Tui<B, S>) -> Result<()> {
+        tui.stop().await?;
+        self.teardown(time::Instant::now() + TEARDOWN_TIMEOUT)
+            .await?;
+        tui.exit().await
+    }
+
+    /// Cancel the running jobs and tear down every component, the ones in overlays first.
+    async fn teardown(&mut self, deadline: time::Instant) -> Result<()> {
+        self.jobs.cancel_all();
+        let roots = self
+            .overlays
+            .iter_mut()
+            .rev()
+            .map(|overlay| &mut overlay.component)
+            .chain(self.components.iter_mut());
+        for root in roots {
+            components::teardown(root.as_mut(), deadline).await?;
+        }
+        Ok(())
+    }
+
+    /// Register the action handler and config with every component, and initialize them.
+    fn init_components(&mut self, area: Rect) -> Result<()> {
+        self.layout.resize(area);
+        let size = area.as_size();
         for component in self.components.iter_mut() {
-            component.register_action_handler(self.action_tx.clone())?;
+            components::walk(component.as_mut(), &mut |component| {
+                component.register_action_handler(self.action_tx.clone())
+            })?;
         }
         for component in self.components.iter_mut() {
-            component.register_config_handler(self.config.clone())?;
+            components::walk(component.as_mut(), &mut |component| {
+                component.register_config_handler(self.config.clone())
+            })?;
         }
         for component in self.components.iter_mut() {
-            component.init(tui.size()?)?;
+            components::walk(component.as_mut(), &mut |component| {
+                component.init(size, self.capabilities)
+            })?;
         }
+        self.update_focus_ring()
+    }
 
+    fn handle_event(&mut self, event: Event) -> Result<()> {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_event(&event)?;
+        }
         let action_tx = self.action_tx.clone();
-        loop {
-            self.handle_events(&mut tui).await?;
-            self.handle_actions(&mut tui)?;
-            if self.should_suspend {
-                tui.suspend()?;
+        if !matches!(event, Event::Tick | Event::Render) {
+            self.dirty = true;
+        }
+        match event {
+            Event::Quit => action_tx.send(Action::Quit)?,
+            Event::Reload => action_tx.send(Action::ReloadConfig)?,
+            Event::Resume => {
                 action_tx.send(Action::Resume)?;
                 action_tx.send(Action::ClearScreen)?;
-                // tui.mouse(true);
-                tui.enter()?;
-            } else if self.should_quit {
-                tui.stop()?;
-                break;
             }
+            Event::Tick => action_tx.send(Action::Tick)?,
+            // the frame rate is only the maximum rate, frames are only drawn when something changed
+            Event::Render if self.needs_render()? => action_tx.send(Action::Render)?,
+            Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
+            Event::FocusLost => self.set_terminal_focused(false)?,
+            Event::FocusGained => self.set_terminal_focused(true)?,
+            // while an overlay is open it captures key events, so they skip the keymap
+            Event::Key(key) if self.overlays.is_empty() => self.handle_key_event(key)?,
+            Event::Mouse(mouse) => return self.handle_mouse_event(mouse),
+            _ => {}
+        }
+        if matches!(event, Event::Key(_) | Event::Paste(_)) {
+            if let Some(overlay) = self.overlays.last_mut() {
+                return Self::handle_overlay_input(overlay, event, &action_tx);
+            }
+        }
+        // key and paste events only go to the focused component, unless no component is focusable
+        let focus = &self.focus;
+        let is_text_input = matches!(event, Event::Key(_) | Event::Paste(_));
+        let router = &self.router;
+        let active = self
+            .components
+            .iter_mut()
+            .filter(|component| router.is_active(component.id()));
+        for component in active {
+            components::walk(component.as_mut(), &mut |component| {
+                if is_text_input && !focus.is_empty() && !focus.is_focused(component.id()) {
+                    return Ok(());
+                }
+                if let Some(action) = component.handle_events(Some(event.clone()))? {
+                    action_tx.send(action)?;
+                }
+                Ok(())
+            })?;
+        }
+        for overlay in self.overlays.iter_mut() {
+            components::walk(overlay.component.as_mut(), &mut |component| {
+                if let Some(action) = component.handle_events(Some(event.clone()))? {
+                    action_tx.send(action)?;
+                }
+                Ok(())
+            })?;
         }
-        tui.exit()?;
         Ok(())
     }
 
-    async fn handle_events(&mut self, tui: &mut Tui) -> Result<()> {
-        let Some(event) = tui.next_event().await else {
+    /// Deliver a mouse event to the topmost component under the pointer, in the coordinates of
+    /// that component.
+    ///
+    /// While a button is held down, events go to the component that it was pressed on, so that a
+    /// drag can leave the area of the component. Gestures recognized from the event follow the
+    /// raw event, and components are told when the pointer moves onto or off them. While an
+    /// overlay is open, only the components in the topmost overlay receive mouse events.
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<()> {
+        let position = Position::new(mouse.column, mouse.row);
+        let hits = self.hits_at(position)?;
+        let target = match (&self.mouse_capture, mouse.kind) {
+            (Some(id), MouseEventKind::Drag(_) | MouseEventKind::Up(_)) => {
+                let layer = self.overlays.len();
+                self.hit_areas
+                    .iter()
+                    .rev()
+                    .find(|hit| hit.layer == layer && hit.id == *id)
+                    .cloned()
+            }
+            _ => hits.first().cloned(),
+        };
+        match mouse.kind {
+            MouseEventKind::Down(_) => {
+                self.mouse_capture = target.as_ref().map(|hit| hit.id.clone());
+                let focusable = hits.iter().find(|hit| hit.focusable);
+                if let Some(hit) = focusable.filter(|_| self.click_to_focus) {
+                    self.action_tx.send(Action::Focus(hit.id.clone()))?;
+                }
+            }
+            MouseEventKind::Up(_) => self.mouse_capture = None,
+            _ => {}
+        }
+        let hovered = hits.first().map(|hit| hit.id.clone());
+        if hovered != self.hovered {
+            if let Some(id) = self.hovered.take() {
+                self.deliver(&id, |component| {
+                    component.handle_gesture_event(Gesture::HoverLeave)
+                })?;
+            }
+            if let Some(hit) = hits.first() {
+                let gesture = Gesture::HoverEnter { position }.local(hit.area);
+                self.deliver(&hit.id, |component| component.handle_gesture_event(gesture))?;
+            }
+            self.hovered = hovered;
+        }
+        let Some(target) = target else {
             return Ok(());
         };
+        // a captured drag can be left of or above its component, where the raw event stops at the
+        // edge, and the drag gesture below reports the signed distance instead
+        let event = Event::Mouse(MouseEvent {
+            column: mouse.column.saturating_sub(target.area.x),
+            row: mouse.row.saturating_sub(target.area.y),
+            ..mouse
+        });
+        self.deliver(&target.id, |component| {
+            component.handle_events(Some(event.clone()))
+        })?;
+        if let Some(gesture) = self.gestures.handle(mouse, Instant::now()) {
+            let gesture = gesture.local(target.area);
+            self.deliver(&target.id, |component| {
+                component.handle_gesture_event(gesture)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Call `handle` on the component with the given id among the ones that receive input, and
+    /// send the action that it returns.
+    fn deliver(
+        &mut self,
+        id: &str,
+        mut handle: impl FnMut(&mut dyn Component) -> Result<Option<Action>>,
+    ) -> Result<()> {
         let action_tx = self.action_tx.clone();
-        match event {
-            Event::Quit => action_tx.send(Action::Quit)?,
-            Event::Tick => action_tx.send(Action::Tick)?,
-            Event::Render => action_tx.send(Action::Render)?,
-            Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
-            Event::Key(key) => self.handle_key_event(key)?,
-            _ => {}
+        let mut delivered = false;
+        for root in self.input_roots() {
+            components::walk(root.as_mut(), &mut |component| {
+                if delivered || component.id() != id {
+                    return Ok(());
+                }
+                delivered = true;
+                if let Some(action) = handle(component)? {
+                    action_tx.send(action)?;
+                }
+                Ok(())
+            })?;
         }
-        for component in self.components.iter_mut() {
+        Ok(())
+    }
+
+    /// The components that were drawn at a position in the topmost layer, topmost first, without
+    /// the ones that let the pointer through at that position.
+    fn hits_at(&mut self, position: Position) -> Result<Vec<HitArea>> {
+        let layer = self.overlays.len();
+        let candidates: Vec<HitArea> = self
+            .hit_areas
+            .iter()
+            .rev()
+            .filter(|hit| hit.layer == layer && hit.area.contains(position))
+            .cloned()
+            .collect();
+        let mut transparent = Vec::new();
+        for root in self.input_roots() {
+            components::walk(root.as_mut(), &mut |component| {
+                let id = component.id();
+                if candidates.iter().any(|hit| hit.id == id) && !component.hit_test(position) {
+                    transparent.push(id.to_string());
+                }
+                Ok(())
+            })?;
+        }
+        Ok(candidates
+            .into_iter()
+            .filter(|hit| !transparent.contains(&hit.id))
+            .collect())
+    }
+
+    /// Call `f` on the component that receives text input: the topmost overlay while one is open,
+    /// otherwise the focused component. Returns none if there is no such component.
+    fn with_input_target<T>(
+        &mut self,
+        f: impl FnOnce(&mut dyn Component) -> Result<T>,
+    ) -> Result<Option<T>> {
+        if let Some(overlay) = self.overlays.last_mut() {
+            return f(overlay.component.as_mut()).map(Some);
+        }
+        let Some(id) = self.focus.focused().map(str::to_string) else {
+            return Ok(None);
+        };
+        let mut f = Some(f);
+        let mut output = None;
+        for root in self.input_roots() {
+            components::walk(root.as_mut(), &mut |component| {
+                if component.id() == id {
+                    if let Some(f) = f.take() {
+                        output = Some(f(component)?);
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(output)
+    }
+
+    /// The roots of the component trees that receive input: the topmost overlay while one is
+    /// open, otherwise the components on the current screen.
+    fn input_roots(&mut self) -> Vec<&mut Box<dyn Component>> {
+        match self.overlays.last_mut() {
+            Some(overlay) => vec![&mut overlay.component],
+            None => {
+                let router = &self.router;
+                self.components
+                    .iter_mut()
+                    .filter(|component| router.is_active(component.id()))
+                    .collect()
+            }
+        }
+    }
+
+    /// Deliver a key or paste event to the topmost overlay only, and close the overlay when `Esc`
+    /// is pressed unless the overlay handles the key itself.
+    fn handle_overlay_input(
+        overlay: &mut OverlayLayer,
+        event: Event,
+        action_tx: &UnboundedSender<Action>,
+    ) -> Result<()> {
+        let mut handled = false;
+        components::walk(overlay.component.as_mut(), &mut |component| {
             if let Some(action) = component.handle_events(Some(event.clone()))? {
+                handled = true;
                 action_tx.send(action)?;
             }
+            Ok(())
+        })?;
+        let is_esc = |key: &KeyEvent| key.code == KeyCode::Esc && key.kind == KeyEventKind::Press;
+        if !handled && matches!(event, Event::Key(key) if is_esc(&key)) {
+            action_tx.send(Action::PopOverlay)?;
         }
         Ok(())
     }
 
     fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        // releases only go to components, and holding a key down repeats its action
+        if key.kind == KeyEventKind::Release {
+            return Ok(());
+        }
+        // the keymap is keyed by presses without any keyboard enhancement state
+        let key = KeyEvent::new(key.code, key.modifiers);
         let action_tx = self.action_tx.clone();
-        let Some(keymap) = self.config.keybindings.get(&self.mode) else {
+        let Some(keymap) = self.config.keybindings.get(&self.router.mode()) else {
             return Ok(());
         };
         match keymap.get(&vec![key]) {
@@ -130,48 +666,917 @@ impl App {
         Ok(())
     }
 
-    fn handle_actions(&mut self, tui: &mut Tui) -> Result<()> {
+    fn handle_actions<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         while let Ok(action) = self.action_rx.try_recv() {
-            if action != Action::Tick && action != Action::Render {
-                debug!("{action:?}");
-            }
-            match action {
-                Action::Tick => {
-                    self.last_tick_key_events.drain(..);
-                }
-                Action::Quit => self.should_quit = true,
-                Action::Suspend => self.should_suspend = true,
-                Action::Resume => self.should_suspend = false,
-                Action::ClearScreen => tui.terminal.clear()?,
-                Action::Resize(w, h) => self.handle_resize(tui, w, h)?,
-                Action::Render => self.render(tui)?,
-                _ => {}
-            }
-            for component in self.components.iter_mut() {
+            self.handle_action(terminal, action)?;
+        }
+        self.update_focus_ring()
+    }
+
+    fn handle_action<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        action: Action,
+    ) -> Result<()> {
+        #[cfg(test)]
+        self.dispatched.push(action.clone());
+        if !self.jobs.accept(&action) {
+            // output of a job run that has been replaced by a newer run
+            return Ok(());
+        }
+        if action != Action::Tick && action != Action::Render {
+            debug!("{action:?}");
+        }
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_action(&action)?;
+        }
+        if !matches!(action, Action::Tick | Action::Render) {
+            self.dirty = true;
+        }
+        match action {
+            Action::Tick => {
+                self.last_tick_key_events.drain(..);
+            }
+            Action::Quit => self.should_quit = true,
+            Action::Error(ref details) => match details.severity {
+                Severity::Info => info!("{details}"),
+                Severity::Warning => warn!("{details}"),
+                Severity::Error => error!("{details}"),
+            },
+            Action::Suspend => self.should_suspend = true,
+            Action::Resume => self.should_suspend = false,
+            Action::ClearScreen => terminal.clear()?,
+            Action::ReloadConfig => self.reload_config()?,
+            Action::Edit { ref id, ref text } => self.edit = Some((id.clone(), text.clone())),
+            Action::RunJob {
+                ref id,
+                ref command,
+            } => {
+                if let Err(err) = self.jobs.spawn(id, command) {
+                    let details = ErrorDetails::from_report(&err);
+                    self.action_tx.send(Action::Error(details))?;
+                }
+            }
+            Action::CancelJob(ref id) => self.jobs.cancel(id),
+            Action::CancelJobs => self.jobs.cancel_all(),
+            Action::Copy(ref text) => {
+                self.register = text.clone();
+                self.copy = Some(text.clone());
+            }
+            Action::CopySelection => {
+                let text = self.with_input_target(|component| Ok(component.selected_text()))?;
+                if let Some(text) = text.flatten() {
+                    self.action_tx.send(Action::Copy(text))?;
+                }
+            }
+            Action::Paste if !self.register.is_empty() => {
+                let text = self.register.clone();
+                let action =
+                    self.with_input_target(|component| component.handle_paste_event(text))?;
+                if let Some(action) = action.flatten() {
+                    self.action_tx.send(action)?;
+                }
+            }
+            Action::Resize(width, height) => self.handle_resize(terminal, width, height)?,
+            Action::Render => self.render(terminal)?,
+            Action::FocusNext => self.change_focus(FocusRing::focus_next)?,
+            Action::FocusPrevious => self.change_focus(FocusRing::focus_previous)?,
+            Action::Focus(ref id) => self.change_focus(|focus| focus.focus(id))?,
+            Action::PushOverlay(ref overlay) => {
+                self.push_overlay(overlay.clone().into_layer(), terminal.size()?)?
+            }
+            Action::Help => {
+                let help = Help::new(self.router.mode());
+                let overlay = OverlayLayer::new(
+                    Box::new(help),
+                    Constraint::Percentage(80),
+                    Constraint::Percentage(80),
+                );
+                self.push_overlay(overlay, terminal.size()?)?;
+            }
+            Action::PopOverlay => {
+                self.overlays.pop();
+            }
+            Action::Navigate(mode) => {
+                self.router.navigate(mode);
+                self.last_tick_key_events.drain(..);
+            }
+            Action::Back => {
+                self.router.back();
+                self.last_tick_key_events.drain(..);
+            }
+            Action::SetTickRate(rate) if rate > 0.0 && rate.is_finite() => {
+                self.rates.tick_rate = Rates::clamp(rate)
+            }
+            Action::SetFrameRate(rate) if rate > 0.0 && rate.is_finite() => {
+                self.rates.frame_rate = Rates::clamp(rate)
+            }
+            Action::SetTickRate(rate) | Action::SetFrameRate(rate) => {
+                warn!("Ignoring invalid rate {rate}")
+            }
+            Action::PauseTicks => self.rates.ticks_paused = true,
+            Action::ResumeTicks => self.rates.ticks_paused = false,
+            _ => {}
+        }
+        // components that are not on the current screen still receive actions so that they
+        // stay up to date while hidden
+        for component in self.components.iter_mut() {
+            components::walk(component.as_mut(), &mut |component| {
                 if let Some(action) = component.update(action.clone())? {
                     self.action_tx.send(action)?
                 };
-            }
+                Ok(())
+            })?;
+        }
+        for overlay in self.overlays.iter_mut() {
+            components::walk(overlay.component.as_mut(), &mut |component| {
+                if let Some(action) = component.update(action.clone())? {
+                    self.action_tx.send(action)?
+                };
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Track whether the terminal is focused, and tell every component when that changes.
+    fn set_terminal_focused(&mut self, focused: bool) -> Result<()> {
+        if self.terminal_focused == focused {
+            return Ok(());
+        }
+        self.terminal_focused = focused;
+        let action_tx = self.action_tx.clone();
+        let overlays = self
+            .overlays
+            .iter_mut()
+            .map(|overlay| &mut overlay.component);
+        for component in self.components.iter_mut().chain(overlays) {
+            components::walk(component.as_mut(), &mut |component| {
+                let action = if focused {
+                    component.on_terminal_focus_gained()?
+                } else {
+                    component.on_terminal_focus_lost()?
+                };
+                if let Some(action) = action {
+                    action_tx.send(action)?;
+                }
+                Ok(())
+            })?;
         }
         Ok(())
     }
 
-    fn handle_resize(&mut self, tui: &mut Tui, w: u16, h: u16) -> Result<()> {
-        tui.resize(Rect::new(0, 0, w, h))?;
-        self.render(tui)?;
+    /// The rates that the event loop should run at, after applying the power policy.
+    fn effective_rates(&self) -> Rates {
+        if self.terminal_focused {
+            return self.rates;
+        }
+        match self.power_policy {
+            PowerPolicy::AlwaysOn => self.rates,
+            PowerPolicy::Throttle => Rates {
+                tick_rate: self.rates.tick_rate.min(1.0),
+                frame_rate: self.rates.frame_rate.min(1.0),
+                ..self.rates
+            },
+            PowerPolicy::Suspend => Rates {
+                ticks_paused: true,
+                frames_paused: true,
+                ..self.rates
+            },
+        }
+    }
+
+    /// The rates that the event loop should run at until the next event or action: the effective
+    /// rates, with frames paused while there is nothing new to draw, so that an idle app sleeps.
+    fn loop_rates(&mut self) -> Result<Rates> {
+        let mut rates = self.effective_rates();
+        rates.frames_paused |= !self.needs_render()?;
+        Ok(rates)
+    }
+
+    /// Whether anything changed since the last frame, either in the app or in a visible component.
+    fn needs_render(&mut self) -> Result<bool> {
+        let mut dirty = self.dirty;
+        let router = &self.router;
+        let active = self
+            .components
+            .iter_mut()
+            .filter(|component| router.is_active(component.id()));
+        let overlays = self
+            .overlays
+            .iter_mut()
+            .map(|overlay| &mut overlay.component);
+        for component in active.chain(overlays) {
+            components::walk(component.as_mut(), &mut |component| {
+                dirty |= component.needs_render();
+                Ok(())
+            })?;
+        }
+        Ok(dirty)
+    }
+
+    /// Register and initialize an overlay, and open it above the base UI and any other overlays.
+    fn push_overlay(&mut self, mut overlay: OverlayLayer, size: Size) -> Result<()> {
+        components::walk(overlay.component.as_mut(), &mut |component| {
+            component.register_action_handler(self.action_tx.clone())?;
+            component.register_config_handler(self.config.clone())?;
+            component.init(size, self.capabilities)
+        })?;
+        self.overlays.push(overlay);
         Ok(())
     }
 
-    fn render(&mut self, tui: &mut Tui) -> Result<()> {
-        tui.draw(|frame| {
-            for component in self.components.iter_mut() {
-                if let Err(err) = component.draw(frame, frame.area()) {
-                    let _ = self
-                        .action_tx
-                        .send(Action::Error(format!("Failed to draw: {:?}", err)));
+    /// Rebuild the focus ring from the focusable components that are currently in the tree, and
+    /// check that they all have their own id.
+    fn update_focus_ring(&mut self) -> Result<()> {
+        let mut ids = Vec::new();
+        let mut seen = HashSet::new();
+        let router = &self.router;
+        let active = self
+            .components
+            .iter_mut()
+            .filter(|component| router.is_active(component.id()));
+        for component in active {
+            components::walk(component.as_mut(), &mut |component| {
+                let id = component.id();
+                if !seen.insert(id.to_string()) {
+                    return Err(eyre!(
+                        "more than one component has the id {id:?}, override Component::id to \
+                         tell them apart"
+                    ));
                 }
+                if component.is_focusable() {
+                    ids.push(id.to_string());
+                }
+                Ok(())
+            })?;
+        }
+        self.change_focus(|focus| focus.set_ids(ids))
+    }
+
+    /// Apply a change to the focus ring and notify the components that lost and gained focus.
+    fn change_focus(&mut self, change: impl FnOnce(&mut FocusRing)) -> Result<()> {
+        let previous = self.focus.focused().map(str::to_string);
+        change(&mut self.focus);
+        let current = self.focus.focused().map(str::to_string);
+        if previous == current {
+            return Ok(());
+        }
+        let action_tx = self.action_tx.clone();
+        for component in self.components.iter_mut() {
+            components::walk(component.as_mut(), &mut |component| {
+                let id = Some(component.id());
+                let action = if id == previous.as_deref() {
+                    component.on_blur()?
+                } else if id == current.as_deref() {
+                    component.on_focus()?
+                } else {
+                    None
+                };
+                if let Some(action) = action {
+                    action_tx.send(action)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Read the configuration again and hand it to every component. If the new configuration is
+    /// invalid, the app keeps using the old one.
+    fn reload_config(&mut self) -> Result<()> {
+        let config = match Config::new() {
+            Ok(config) => config,
+            Err(err) => {
+                let details = ErrorDetails::new(format!("Failed to reload the config: {err}"));
+                self.action_tx.send(Action::Error(details))?;
+                return Ok(());
+            }
+        };
+        info!("Reloaded the config");
+        self.config = config;
+        let roots = self.components.iter_mut().chain(
+            self.overlays
+                .iter_mut()
+                .map(|overlay| &mut overlay.component),
+        );
+        for root in roots {
+            components::walk(root.as_mut(), &mut |component| {
+                component.register_config_handler(self.config.clone())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn handle_resize<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        width: u16,
+        height: u16,
+    ) -> Result<()> {
+        if self.replay_sized {
+            // the fixed viewport of a replay follows the recorded size, not the terminal
+            terminal.resize(Rect::new(0, 0, width, height))?;
+        } else {
+            // the terminal decides how its viewport follows the new size, e.g. a fixed viewport
+            // doesn't
+            terminal.autoresize()?;
+        }
+        self.layout.resize(terminal.get_frame().area());
+        self.render(terminal)?;
+        Ok(())
+    }
+
+    fn render<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        self.hit_areas.clear();
+        terminal.draw(|frame| {
+            let area = frame.area();
+            let router = &self.router;
+            let active = self
+                .components
+                .iter_mut()
+                .filter(|component| router.is_active(component.id()));
+            for component in active {
+                Self::draw_component(
+                    component.as_mut(),
+                    frame,
+                    area,
+                    &self.layout,
+                    &self.action_tx,
+                    &mut self.hit_areas,
+                    0,
+                );
+            }
+            for (index, overlay) in self.overlays.iter_mut().enumerate() {
+                let area = overlay.area(area);
+                frame.render_widget(Clear, area);
+                Self::draw_component(
+                    overlay.component.as_mut(),
+                    frame,
+                    area,
+                    &self.layout,
+                    &self.action_tx,
+                    &mut self.hit_areas,
+                    index + 1,
+                );
             }
         })?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Draw a component and then its children, in the areas that the component lays them out in.
+    ///
+    /// Components that are assigned to a slot in the layout registry are drawn in that slot
+    /// instead of the area given by their parent. The area of every component is remembered for
+    /// routing mouse events.
+    fn draw_component(
+        component: &mut dyn Component,
+        frame: &mut Frame,
+        area: Rect,
+        layout: &LayoutRegistry,
+        action_tx: &UnboundedSender<Action>,
+        hit_areas: &mut Vec<HitArea>,
+        layer: usize,
+    ) {
+        let area = layout.area_of(component.id()).unwrap_or(area);
+        hit_areas.push(HitArea {
+            id: component.id().to_string(),
+            area,
+            focusable: component.is_focusable(),
+            layer,
+        });
+        if let Err(err) = component.draw(frame, area) {
+            let details = ErrorDetails::from_report(&err.wrap_err("Failed to draw"));
+            let _ = action_tx.send(Action::Error(details.component(component.id())));
+        }
+        let areas = component.layout_children(area);
+        for (index, child) in component.children().into_iter().enumerate() {
+            let child_area = areas.get(index).copied().unwrap_or(area);
+            Self::draw_component(
+                child, frame, child_area, layout, action_tx, hit_areas, layer,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+    use crossterm::event::{KeyModifiers, MouseButton};
+    use futures::future::{self, FutureExt, LocalBoxFuture};
+    use pretty_assertions::assert_eq;
+    use ratatui::layout::Layout;
+
+    use super::*;
+    use crate::{
+        action::Severity,
+        app::harness::{Harness, Scripted},
+    };
+
+    struct Poller;
+
+    impl Component for Poller {
+        fn on_terminal_focus_lost(&mut self) -> Result<Option<Action>> {
+            Ok(Some(Action::Notify(Severity::Info, "paused".into())))
+        }
+
+        fn on_terminal_focus_gained(&mut self) -> Result<Option<Action>> {
+            Ok(Some(Action::Notify(Severity::Info, "resumed".into())))
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_terminal_focus_callbacks() -> Result<()> {
+        let mut harness = Harness::with_components(vec![Box::new(Poller)], 10, 2).await?;
+        harness
+            .events([Event::FocusLost, Event::FocusLost, Event::FocusGained])
+            .await?;
+        assert_eq!(
+            harness.take_actions(),
+            [
+                Action::Notify(Severity::Info, "paused".into()),
+                Action::Notify(Severity::Info, "resumed".into()),
+            ]
+        );
+        Ok(())
+    }
+
+    /// A pane that reports where it was clicked and which keys it got.
+    struct Pane(&'static str);
+
+    impl Component for Pane {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn is_focusable(&self) -> bool {
+            true
+        }
+
+        fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+            let message = format!("{} {}", self.0, key.code);
+            Ok(Some(Action::Notify(Severity::Info, message)))
+        }
+
+        fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+            let message = format!("{} {},{}", self.0, mouse.column, mouse.row);
+            Ok(Some(Action::Notify(Severity::Info, message)))
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A pane that reports the gestures it receives.
+    struct GesturePane(&'static str);
+
+    impl Component for GesturePane {
+        fn id(&self) -> &str {
+            self.0
+        }
+
+        fn handle_gesture_event(&mut self, gesture: Gesture) -> Result<Option<Action>> {
+            let gesture = match gesture {
+                Gesture::Click {
+                    count, position, ..
+                } => format!("click{count} {position}"),
+                Gesture::DragStart {
+                    origin, position, ..
+                } => format!("drag {origin} {position}"),
+                Gesture::DragMove { position, .. } => format!("move {position}"),
+                Gesture::DragEnd {
+                    position, delta, ..
+                } => format!("drop {position} by {},{}", delta.x, delta.y),
+                Gesture::HoverEnter { position } => format!("enter {position}"),
+                Gesture::HoverLeave => "leave".into(),
+            };
+            let message = format!("{} {gesture}", self.0);
+            Ok(Some(Action::Notify(Severity::Info, message)))
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    struct Split<C: Component>(Vec<C>);
+
+    impl<C: Component> Component for Split<C> {
+        fn children(&mut self) -> Vec<&mut dyn Component> {
+            self.0
+                .iter_mut()
+                .map(|pane| pane as &mut dyn Component)
+                .collect()
+        }
+
+        fn layout_children(&mut self, area: Rect) -> Vec<Rect> {
+            Layout::horizontal([Constraint::Fill(1); 2])
+                .split(area)
+                .to_vec()
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> Event {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::empty(),
+        })
+    }
+
+    fn click(column: u16, row: u16) -> Event {
+        mouse(MouseEventKind::Down(MouseButton::Left), column, row)
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_ids_are_rejected() -> Result<()> {
+        let pollers: Vec<Box<dyn Component>> = vec![Box::new(Poller), Box::new(Poller)];
+        let Err(err) = Harness::with_components(pollers, 10, 2).await else {
+            panic!("two components with the same id were accepted");
+        };
+        assert!(err.to_string().contains("\"Poller\""));
+
+        let panes: Vec<Box<dyn Component>> = vec![Box::new(Pane("left")), Box::new(Pane("left"))];
+        assert!(Harness::with_components(panes, 10, 2).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keys_go_to_focused_component() -> Result<()> {
+        let panes: Vec<Box<dyn Component>> = vec![Box::new(Pane("left")), Box::new(Pane("right"))];
+        let mut harness = Harness::with_components(panes, 10, 2).await?;
+        harness.take_actions();
+        let notify = |message: &str| Action::Notify(Severity::Info, message.into());
+
+        harness.key(KeyCode::Char('x')).await?;
+        assert_eq!(harness.take_actions(), [notify("left x")]);
+
+        harness.action(Action::FocusNext).await?.take_actions();
+        harness.key(KeyCode::Char('x')).await?;
+        assert_eq!(harness.take_actions(), [notify("right x")]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mouse_goes_to_component_under_pointer() -> Result<()> {
+        let split = Split(vec![Pane("left"), Pane("right")]);
+        let mut harness = Harness::with_components(vec![Box::new(split)], 10, 2).await?;
+        harness.render().await?.take_actions();
+
+        harness.event(click(7, 1)).await?;
+        assert_eq!(
+            harness.take_actions(),
+            [
+                Action::Focus("right".into()),
+                Action::Notify(Severity::Info, "right 2,1".into()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gestures() -> Result<()> {
+        let split = Split(vec![GesturePane("left"), GesturePane("right")]);
+        let mut harness = Harness::with_components(vec![Box::new(split)], 10, 2).await?;
+        harness.render().await?.take_actions();
+
+        let notify = |message: &str| Action::Notify(Severity::Info, message.into());
+        harness.event(mouse(MouseEventKind::Moved, 1, 0)).await?;
+        assert_eq!(harness.take_actions(), [notify("left enter (1, 0)")]);
+
+        harness.events([click(2, 0), click(2, 0)]).await?;
+        assert_eq!(
+            harness.take_actions(),
+            [notify("left click1 (2, 0)"), notify("left click2 (2, 0)")]
+        );
+
+        // a drag stays with the component where it started
+        let drag = MouseEventKind::Drag(MouseButton::Left);
+        harness
+            .events([
+                mouse(drag, 4, 1),
+                mouse(drag, 7, 1),
+                mouse(MouseEventKind::Up(MouseButton::Left), 7, 1),
+            ])
+            .await?;
+        assert_eq!(
+            harness.take_actions(),
+            [
+                notify("left drag (2, 0) (4, 1)"),
+                notify("left leave"),
+                notify("right enter (2, 1)"),
+                notify("left move (7, 1)"),
+                notify("left drop (7, 1) by 5,1"),
+            ]
+        );
+
+        // a drag that leaves to the left still reports how far it went
+        harness
+            .events([
+                click(6, 0),
+                mouse(drag, 2, 0),
+                mouse(MouseEventKind::Up(MouseButton::Left), 1, 0),
+            ])
+            .await?;
+        assert_eq!(
+            harness.take_actions().last(),
+            Some(&notify("right drop (0, 0) by -5,0"))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_click_to_focus_can_be_disabled() -> Result<()> {
+        let mut app = App::new(4.0, 60.0)?.click_to_focus(false);
+        app.components = vec![Box::new(Pane("pane")), Box::new(Notifications::new())];
+        let mut harness = Harness::with_app(app, 10, 2).await?;
+        harness.render().await?.take_actions();
+
+        // the notifications are drawn on top, but let the pointer through outside of their toasts
+        harness.event(click(3, 1)).await?;
+        assert_eq!(
+            harness.take_actions(),
+            [Action::Notify(Severity::Info, "pane 3,1".into())]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keymap_ignores_key_release() -> Result<()> {
+        let mut harness = Harness::new(10, 2).await?;
+        let release = KeyEvent::new_with_kind(
+            KeyCode::Char('q'),
+            KeyModifiers::empty(),
+            KeyEventKind::Release,
+        );
+        harness.event(Event::Key(release)).await?;
+        assert!(!harness.should_quit());
+
+        let repeat = KeyEvent::new_with_kind(
+            KeyCode::Char('q'),
+            KeyModifiers::empty(),
+            KeyEventKind::Repeat,
+        );
+        harness.event(Event::Key(repeat)).await?;
+        assert!(harness.should_quit());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_signal_events() -> Result<()> {
+        let mut harness = Harness::new(10, 2).await?;
+        harness.events([Event::Reload, Event::Resume]).await?;
+        assert_eq!(
+            harness.take_actions(),
+            [Action::ReloadConfig, Action::Resume, Action::ClearScreen]
+        );
+        assert!(!harness.should_quit());
+
+        harness.event(Event::Quit).await?;
+        assert!(harness.should_quit());
+        Ok(())
+    }
+
+    /// A focusable component that has some text selected and reports what is pasted into it.
+    struct Editor;
+
+    impl Component for Editor {
+        fn is_focusable(&self) -> bool {
+            true
+        }
+
+        fn selected_text(&self) -> Option<String> {
+            Some("selected".into())
+        }
+
+        fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+            Ok(Some(Action::Notify(Severity::Info, text)))
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_copy_selection_and_paste() -> Result<()> {
+        let mut app = App::new(4.0, 60.0)?;
+        app.components = vec![Box::new(Editor)];
+        let mut harness = Harness::with_app(app, 10, 2).await?;
+        harness.take_actions();
+        let ctrl = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL));
+        // nothing has been copied yet
+        harness.event(ctrl('v')).await?;
+        assert_eq!(harness.take_actions(), [Action::Paste]);
+
+        harness.events([ctrl('y'), ctrl('v')]).await?;
+        assert_eq!(
+            harness.take_actions(),
+            [
+                Action::CopySelection,
+                Action::Copy("selected".into()),
+                Action::Paste,
+                Action::Notify(Severity::Info, "selected".into()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_power_policy_while_unfocused() -> Result<()> {
+        let mut app = App::new(4.0, 60.0)?;
+        app.set_terminal_focused(false)?;
+        assert_eq!(app.effective_rates().tick_rate, 1.0);
+        assert_eq!(app.effective_rates().frame_rate, 1.0);
+
+        let mut app = app.power_policy(PowerPolicy::Suspend);
+        assert!(app.effective_rates().ticks_paused);
+        assert!(app.effective_rates().frames_paused);
+
+        app.set_terminal_focused(true)?;
+        assert_eq!(app.effective_rates(), app.rates);
+        Ok(())
+    }
+
+    #[test]
+    fn test_frames_pause_while_clean() -> Result<()> {
+        let mut app = App::new(4.0, 60.0)?;
+        assert!(!app.loop_rates()?.frames_paused);
+        app.dirty = false;
+        assert!(app.loop_rates()?.frames_paused);
+        assert!(!app.loop_rates()?.ticks_paused);
+
+        app.handle_event(Event::Key(KeyEvent::from(KeyCode::Char('x'))))?;
+        assert!(!app.loop_rates()?.frames_paused);
+
+        // unless a component draws every frame
+        let mut app = App::new(4.0, 60.0)?.show_fps(true);
+        app.dirty = false;
+        assert!(!app.loop_rates()?.frames_paused);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extreme_rates_are_clamped() -> Result<()> {
+        let mut harness = Harness::new(10, 2).await?;
+        harness
+            .action(Action::SetFrameRate(1e10))
+            .await?
+            .action(Action::SetTickRate(1e-300))
+            .await?
+            .action(Action::SetTickRate(f64::NAN))
+            .await?;
+        assert_eq!(harness.app().rates.frame_rate, Rates::MAX_RATE);
+        assert_eq!(harness.app().rates.tick_rate, Rates::MIN_RATE);
+        // the event loop runs at the new rates
+        assert_eq!(harness.tui().rates().frame_rate, Rates::MAX_RATE);
+        assert_eq!(harness.tui().rates().tick_rate, Rates::MIN_RATE);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_navigate_swaps_screen_and_keymap() -> Result<()> {
+        let mut app = App::new(4.0, 60.0)?;
+        app.components = vec![
+            Box::new(Pane("home")),
+            Box::new(Pane("left")),
+            Box::new(Pane("right")),
+        ];
+        app.router = Router::new(Mode::Home)
+            .screen(Mode::Home, ["home"])
+            .screen(Mode::About, ["left", "right"]);
+        let bind = |code: KeyCode, action| HashMap::from([(vec![KeyEvent::from(code)], action)]);
+        app.config.keybindings.0 = HashMap::from([
+            (
+                Mode::Home,
+                bind(KeyCode::Enter, Action::Navigate(Mode::About)),
+            ),
+            (Mode::About, bind(KeyCode::Esc, Action::Back)),
+        ]);
+        let mut harness = Harness::with_app(app, 10, 2).await?;
+        let focused = |harness: &Harness| harness.app().focus.focused().map(str::to_string);
+        assert_eq!(focused(&harness).as_deref(), Some("home"));
+
+        // each mode only has its own keymap
+        harness.key(KeyCode::Esc).await?;
+        assert_eq!(harness.app().router.mode(), Mode::Home);
+        harness.key(KeyCode::Enter).await?;
+        assert_eq!(harness.app().router.mode(), Mode::About);
+        assert_eq!(focused(&harness).as_deref(), Some("left"));
+        harness.key(KeyCode::Enter).await?;
+        assert_eq!(harness.app().router.mode(), Mode::About);
+
+        harness.action(Action::FocusNext).await?;
+        assert_eq!(focused(&harness).as_deref(), Some("right"));
+        harness.key(KeyCode::Esc).await?;
+        assert_eq!(harness.app().router.mode(), Mode::Home);
+        assert_eq!(focused(&harness).as_deref(), Some("home"));
+        assert!(harness.app().router.history().is_empty());
+        Ok(())
+    }
+
+    /// A component that tells when it has been torn down.
+    struct Teardown(Rc<Cell<bool>>);
+
+    impl Component for Teardown {
+        fn teardown(&mut self, _deadline: time::Instant) -> LocalBoxFuture<'_, Result<()>> {
+            self.0.set(true);
+            future::ok(()).boxed_local()
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quit_tears_down_and_exits() -> Result<()> {
+        let torn_down = Rc::new(Cell::new(false));
+        let teardown = Teardown(torn_down.clone());
+        let mut harness = Harness::with_components(vec![Box::new(teardown)], 10, 2).await?;
+        assert_eq!(harness.tui().source.entered, 1);
+
+        harness.action(Action::Quit).await?;
+        assert!(harness.should_quit());
+        assert!(torn_down.get());
+        assert!(harness.tui().task.is_finished());
+        assert_eq!(harness.tui().source.exited, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_suspend_and_resume() -> Result<()> {
+        let mut harness = Harness::new(10, 2).await?;
+        harness.take_actions();
+        harness.action(Action::Suspend).await?;
+        assert_eq!(
+            harness.take_actions(),
+            [Action::Suspend, Action::Resume, Action::ClearScreen]
+        );
+        let source = &harness.tui().source;
+        assert_eq!(source.suspended, 1);
+        // the terminal is restored before suspending, and set up again afterwards
+        assert_eq!(source.exited, 1);
+        assert_eq!(source.entered, 2);
+        assert!(!harness.tui().task.is_finished());
+        assert!(!harness.should_quit());
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_edit_releases_terminal() -> Result<()> {
+        let app = App::new(4.0, 60.0)?.editor("true");
+        let mut harness = Harness::with_app(app, 10, 2).await?;
+        harness.take_actions();
+        let edit = Action::Edit {
+            id: "Home".into(),
+            text: "text".into(),
+        };
+        harness.action(edit.clone()).await?;
+        assert_eq!(
+            harness.take_actions(),
+            [
+                edit,
+                Action::Edited {
+                    id: "Home".into(),
+                    text: "text".into(),
+                },
+            ]
+        );
+        assert_eq!(harness.tui().source.exited, 1);
+        assert_eq!(harness.tui().source.entered, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_copy_needs_clipboard() -> Result<()> {
+        let mut harness = Harness::new(10, 2).await?;
+        harness.action(Action::Copy("text".into())).await?;
+        assert!(harness.tui().source.clipboard.is_empty());
+
+        let source = Scripted {
+            capabilities: Capabilities {
+                clipboard: true,
+                ..Capabilities::default()
+            },
+            ..Scripted::default()
+        };
+        let mut harness = Harness::with_source(App::new(4.0, 60.0)?, source, 10, 2).await?;
+        harness.action(Action::Copy("text".into())).await?;
+        assert_eq!(harness.tui().source.clipboard, ["text"]);
         Ok(())
     }
 }
@@ -0,0 +1,206 @@
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use futures::{stream::BoxStream, StreamExt};
+use ratatui::{backend::TestBackend, buffer::Buffer};
+
+use super::App;
+use crate::{
+    action::Action,
+    capabilities::Capabilities,
+    components::Component,
+    tui::{Event, EventSource, Features, Tui},
+};
+
+/// An event source that doesn't touch the terminal, and keeps track of what the tui asks of it.
+#[derive(Debug, Default)]
+pub struct Scripted {
+    /// The events that the source sends when the event loop first starts.
+    pub events: Vec<Event>,
+    pub capabilities: Capabilities,
+    /// The texts that were copied to the clipboard.
+    pub clipboard: Vec<String>,
+    pub entered: usize,
+    pub exited: usize,
+    pub suspended: usize,
+}
+
+impl Scripted {
+    pub fn new(events: Vec<Event>) -> Self {
+        Self {
+            events,
+            ..Self::default()
+        }
+    }
+}
+
+impl EventSource for Scripted {
+    fn enter(&mut self, _features: Features) -> Result<()> {
+        self.entered += 1;
+        Ok(())
+    }
+
+    fn exit(&mut self, _features: Features) -> Result<()> {
+        self.exited += 1;
+        Ok(())
+    }
+
+    fn events(&mut self) -> BoxStream<'static, Event> {
+        futures::stream::iter(std::mem::take(&mut self.events))
+            .chain(futures::stream::pending())
+            .boxed()
+    }
+
+    fn capabilities(&mut self) -> Result<Capabilities> {
+        Ok(self.capabilities)
+    }
+
+    fn set_clipboard(&mut self, text: &str) -> Result<bool> {
+        self.clipboard.push(text.to_string());
+        Ok(true)
+    }
+
+    fn suspend(&mut self) -> Result<()> {
+        self.suspended += 1;
+        Ok(())
+    }
+}
+
+/// Drives an [`App`] through a [`Tui`] with a [`TestBackend`] and a [`Scripted`] event source.
+///
+/// Every event goes through the same loop as in [`App::run_with`], and the actions that it causes
+/// are dispatched straight away, so tests can assert on the rendered [`Buffer`], on the emitted
+/// [`Action`]s and on what the tui did with the terminal after every step. Ticks and frames are
+/// only sent when a test asks for them.
+pub struct Harness {
+    app: App,
+    tui: Tui<TestBackend, Scripted>,
+    quit: bool,
+}
+
+impl Harness {
+    /// Create a harness for the default app.
+    pub async fn new(width: u16, height: u16) -> Result<Self> {
+        Self::with_app(App::new(4.0, 60.0)?, width, height).await
+    }
+
+    /// Create a harness for an app that only contains the given components.
+    pub async fn with_components(
+        components: Vec<Box<dyn Component>>,
+        width: u16,
+        height: u16,
+    ) -> Result<Self> {
+        let mut app = App::new(4.0, 60.0)?;
+        app.components = components;
+        Self::with_app(app, width, height).await
+    }
+
+    pub async fn with_app(app: App, width: u16, height: u16) -> Result<Self> {
+        Self::with_source(app, Scripted::default(), width, height).await
+    }
+
+    /// Create a harness whose tui reads from the given source, e.g. to pretend that the terminal
+    /// has some capabilities.
+    pub async fn with_source(
+        mut app: App,
+        source: Scripted,
+        width: u16,
+        height: u16,
+    ) -> Result<Self> {
+        app.rates.ticks_paused = true;
+        app.rates.frames_paused = true;
+        let tui = Tui::with_backend(TestBackend::new(width, height), source)?;
+        let mut harness = Self {
+            app,
+            tui,
+            quit: false,
+        };
+        harness.app.start(&mut harness.tui)?;
+        // wait for the event loop to send Event::Init
+        while harness.tui.event_rx.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        harness.settle().await?;
+        Ok(harness)
+    }
+
+    /// Handle an event and dispatch every action that it causes.
+    pub async fn event(&mut self, event: Event) -> Result<&mut Self> {
+        self.tui.event_tx.send(event)?;
+        self.settle().await?;
+        Ok(self)
+    }
+
+    /// Handle a sequence of events, one at a time.
+    pub async fn events(&mut self, events: impl IntoIterator<Item = Event>) -> Result<&mut Self> {
+        for event in events {
+            self.event(event).await?;
+        }
+        Ok(self)
+    }
+
+    /// Dispatch an action, as if a component had sent it, and every action that it causes.
+    pub async fn action(&mut self, action: Action) -> Result<&mut Self> {
+        self.app.action_tx.send(action)?;
+        self.settle().await?;
+        Ok(self)
+    }
+
+    pub async fn key(&mut self, code: KeyCode) -> Result<&mut Self> {
+        self.event(Event::Key(KeyEvent::from(code))).await
+    }
+
+    pub async fn tick(&mut self) -> Result<&mut Self> {
+        self.event(Event::Tick).await
+    }
+
+    pub async fn render(&mut self) -> Result<&mut Self> {
+        self.event(Event::Render).await
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    pub fn tui(&self) -> &Tui<TestBackend, Scripted> {
+        &self.tui
+    }
+
+    /// Whether the app has quit, and has torn down its components and exited the tui.
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn buffer(&self) -> &Buffer {
+        self.tui.backend().buffer()
+    }
+
+    /// The text of a line of the buffer, without styles.
+    pub fn line(&self, y: u16) -> String {
+        let buffer = self.buffer();
+        (buffer.area.left()..buffer.area.right())
+            .map(|x| buffer[(x, y)].symbol())
+            .collect()
+    }
+
+    /// Take the actions that were dispatched since the last call.
+    pub fn take_actions(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.app.dispatched)
+    }
+
+    /// Step the app until it has handled every event and action that is waiting, and shut it down
+    /// like [`App::run_with`] once it quits.
+    async fn settle(&mut self) -> Result<()> {
+        while !self.quit {
+            // let the event loop catch up, e.g. send Event::Init after the tui was resumed
+            tokio::task::yield_now().await;
+            if self.tui.event_rx.is_empty() && self.app.action_rx.is_empty() {
+                break;
+            }
+            if self.app.step(&mut self.tui).await? {
+                self.app.shut_down(&mut self.tui).await?;
+                self.quit = true;
+            }
+        }
+        Ok(())
+    }
+}
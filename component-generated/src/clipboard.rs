@@ -0,0 +1,40 @@
+use std::env;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// The OSC 52 escape sequence that puts `text` on the system clipboard.
+///
+/// Inside tmux (`$TMUX` is set) the sequence is wrapped so that tmux passes it on to the terminal
+/// that it runs in. This needs `set -g allow-passthrough on` in the tmux config.
+pub fn osc52(text: &str) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if env::var_os("TMUX").is_some() {
+        tmux_passthrough(&sequence)
+    } else {
+        sequence
+    }
+}
+
+/// Wrap an escape sequence in a tmux passthrough sequence, doubling the escape characters in it.
+fn tmux_passthrough(sequence: &str) -> String {
+    format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_osc52() {
+        let sequence = "\x1b]52;c;aGVsbG8=\x07";
+        assert_eq!(
+            tmux_passthrough(sequence),
+            "\x1bPtmux;\x1b\x1b]52;c;aGVsbG8=\x07\x1b\\"
+        );
+        if env::var_os("TMUX").is_none() {
+            assert_eq!(osc52("hello"), sequence);
+        }
+    }
+}
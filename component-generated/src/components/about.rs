@@ -0,0 +1,69 @@
+use color_eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::capabilities::Capabilities;
+
+/// A second screen, as an example of navigating between modes: `<a>` opens it from the home
+/// screen and `<Esc>` goes back.
+///
+/// It shows the version of the app and what the terminal supports.
+#[derive(Debug, Default)]
+pub struct About {
+    capabilities: Capabilities,
+}
+
+impl About {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Component for About {
+    fn init(&mut self, _area: Size, capabilities: Capabilities) -> Result<()> {
+        self.capabilities = capabilities;
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let lines = vec![
+            Line::from(concat!(
+                env!("CARGO_PKG_NAME"),
+                " ",
+                env!("CARGO_PKG_VERSION")
+            )),
+            Line::from(format!("Terminal supports: {}", self.capabilities)),
+            Line::from("Press <Esc> to go back").dim(),
+        ];
+        frame.render_widget(Paragraph::new(lines), area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{
+        action::Action,
+        app::{harness::Harness, Mode},
+    };
+
+    #[tokio::test]
+    async fn test_navigate_to_about_and_back() -> Result<()> {
+        let mut harness = Harness::new(40, 3).await?;
+        harness.key(KeyCode::Char('a')).await?.render().await?;
+        assert_eq!(
+            harness.take_actions(),
+            [Action::Navigate(Mode::About), Action::Render]
+        );
+        assert!(harness.line(1).starts_with(env!("CARGO_PKG_NAME")));
+
+        harness.key(KeyCode::Esc).await?.render().await?;
+        assert_eq!(harness.take_actions(), [Action::Back, Action::Render]);
+        assert_eq!(harness.line(1), format!("{:40}", "hello world"));
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::{
+    action::Action,
+    app::Mode,
+    config::{key_event_to_string, Config},
+};
+
+/// A searchable table of the keybindings of a mode, shown as an overlay for [`Action::Help`].
+///
+/// The table is built from the live keymap in the config, so it always matches the keybindings
+/// that are actually in effect. Descriptions come from the `descriptions` section of the config.
+#[derive(Default)]
+pub struct Help {
+    mode: Mode,
+    rows: Vec<HelpRow>,
+    query: String,
+    state: TableState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HelpRow {
+    keys: String,
+    action: String,
+    description: String,
+}
+
+impl HelpRow {
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        [&self.keys, &self.action, &self.description]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+impl Help {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    fn visible_rows<'a>(rows: &'a [HelpRow], query: &'a str) -> impl Iterator<Item = &'a HelpRow> {
+        rows.iter().filter(move |row| row.matches(query))
+    }
+}
+
+impl Component for Help {
+    fn register_config_handler(&mut self, config: Config) -> Result<()> {
+        let Some(keymap) = config.keybindings.get(&self.mode) else {
+            return Ok(());
+        };
+        self.rows = keymap
+            .iter()
+            .map(|(keys, action)| {
+                let action = action.to_string();
+                HelpRow {
+                    keys: keys
+                        .iter()
+                        .map(|key| format!("<{}>", key_event_to_string(key)))
+                        .collect(),
+                    description: config
+                        .descriptions
+                        .get(&action)
+                        .cloned()
+                        .unwrap_or_default(),
+                    action,
+                }
+            })
+            .collect();
+        self.rows
+            .sort_by(|a, b| (&a.action, &a.keys).cmp(&(&b.action, &b.keys)));
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        match key.code {
+            KeyCode::Down => self.state.select_next(),
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.state.select(None);
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.state.select(None);
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+        // the search is a single line
+        self.query
+            .extend(text.lines().flat_map(|line| line.chars()));
+        self.state.select(None);
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let block = Block::bordered().title(format!("Help ({:?})", self.mode));
+        let [search, table] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(block.inner(area));
+        frame.render_widget(block, area);
+
+        let search_line = Line::from(vec!["Search: ".dim(), self.query.as_str().into()]);
+        frame.render_widget(search_line, search);
+
+        let rows = Self::visible_rows(&self.rows, &self.query).map(|row| {
+            Row::new([
+                row.keys.as_str(),
+                row.action.as_str(),
+                row.description.as_str(),
+            ])
+        });
+        let widths = [
+            Constraint::Length(16),
+            Constraint::Length(16),
+            Constraint::Fill(1),
+        ];
+        let table_widget = Table::new(rows, widths)
+            .header(Row::new(["Key", "Action", "Description"]).bold())
+            .row_highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(table_widget, table, &mut self.state);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::tui::Event;
+
+    #[test]
+    fn test_rows_come_from_keymap() -> Result<()> {
+        let mut help = Help::new(Mode::Home);
+        help.register_config_handler(Config::new()?)?;
+        let quit = help
+            .rows
+            .iter()
+            .find(|row| row.keys == "<q>")
+            .expect("q is bound in the default config");
+        assert_eq!(quit.action, "Quit");
+        assert_eq!(quit.description, "Quit the application");
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_filters_rows() -> Result<()> {
+        let mut help = Help::new(Mode::Home);
+        help.register_config_handler(Config::new()?)?;
+        for c in "suspend".chars() {
+            help.handle_key_event(KeyEvent::from(KeyCode::Char(c)))?;
+        }
+        let keys: Vec<_> = Help::visible_rows(&help.rows, &help.query)
+            .map(|row| &row.keys)
+            .collect();
+        assert_eq!(keys, ["<ctrl-z>"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_paste_into_search() -> Result<()> {
+        let mut help = Help::new(Mode::Home);
+        help.register_config_handler(Config::new()?)?;
+        help.handle_events(Some(Event::Paste("sus\npend".into())))?;
+        assert_eq!(help.query, "suspend");
+        Ok(())
+    }
+}
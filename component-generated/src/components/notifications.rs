@@ -0,0 +1,165 @@
+#![allow(dead_code)] // Remove this once you start using the code
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use color_eyre::Result;
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::Component;
+use crate::action::{Action, Severity};
+
+/// The maximum number of notifications that are shown at the same time.
+const MAX_VISIBLE: usize = 5;
+
+/// Shows [`Action::Notify`] and [`Action::Error`] messages as toasts in the bottom right corner.
+///
+/// Notifications are queued and expire on [`Action::Tick`] once they have been shown for long
+/// enough. Errors stay on screen longer than other messages.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    queue: VecDeque<Notification>,
+    /// Where the toasts were drawn in the last frame.
+    toasts: Vec<Rect>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Notification {
+    severity: Severity,
+    message: String,
+    expires_at: Instant,
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&mut self, severity: Severity, message: String) {
+        let ttl = match severity {
+            Severity::Info | Severity::Warning => Duration::from_secs(3),
+            Severity::Error => Duration::from_secs(6),
+        };
+        self.queue.push_back(Notification {
+            severity,
+            message,
+            expires_at: Instant::now() + ttl,
+        });
+    }
+
+    /// Remove the notifications that have expired, and return whether there were any.
+    fn expire(&mut self, now: Instant) -> bool {
+        let len = self.queue.len();
+        self.queue
+            .retain(|notification| notification.expires_at > now);
+        self.queue.len() != len
+    }
+}
+
+impl Component for Notifications {
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::Tick if self.expire(Instant::now()) => return Ok(Some(Action::RequestRender)),
+            Action::Notify(severity, message) => self.push(severity, message),
+            Action::Error(details) => self.push(details.severity, details.message),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn hit_test(&self, position: Position) -> bool {
+        self.toasts.iter().any(|toast| toast.contains(position))
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        self.toasts.clear();
+        let width = area.width.min(50);
+        let mut bottom = area.bottom();
+        for notification in self.queue.iter().rev().take(MAX_VISIBLE) {
+            if bottom < area.top() + 3 {
+                break;
+            }
+            bottom -= 3;
+            let toast = Rect::new(area.right() - width, bottom, width, 3);
+            self.toasts.push(toast);
+            let color = match notification.severity {
+                Severity::Info => Color::Blue,
+                Severity::Warning => Color::Yellow,
+                Severity::Error => Color::Red,
+            };
+            let block = Block::bordered()
+                .border_style(color)
+                .title(notification.severity.to_string());
+            frame.render_widget(Clear, toast);
+            frame.render_widget(
+                Paragraph::new(notification.message.as_str()).block(block),
+                toast,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Post notifications through the action sender that a component registered in
+/// [`Component::register_action_handler`].
+pub trait Notify {
+    fn notify(&self, severity: Severity, message: impl Into<String>) -> Result<()>;
+
+    fn info(&self, message: impl Into<String>) -> Result<()> {
+        self.notify(Severity::Info, message)
+    }
+
+    fn warn(&self, message: impl Into<String>) -> Result<()> {
+        self.notify(Severity::Warning, message)
+    }
+
+    fn error(&self, message: impl Into<String>) -> Result<()> {
+        self.notify(Severity::Error, message)
+    }
+}
+
+impl Notify for UnboundedSender<Action> {
+    fn notify(&self, severity: Severity, message: impl Into<String>) -> Result<()> {
+        self.send(Action::Notify(severity, message.into()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::errors::ErrorDetails;
+
+    #[test]
+    fn test_notify_sends_action() -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tx.warn("careful")?;
+        assert_eq!(
+            rx.try_recv()?,
+            Action::Notify(Severity::Warning, "careful".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_notifications_expire() -> Result<()> {
+        let mut notifications = Notifications::new();
+        notifications.update(Action::Notify(Severity::Info, "info".into()))?;
+        notifications.update(Action::Error(ErrorDetails::new("error")))?;
+        assert_eq!(notifications.queue.len(), 2);
+
+        assert!(notifications.expire(Instant::now() + Duration::from_secs(4)));
+        assert_eq!(notifications.queue.len(), 1);
+        assert_eq!(notifications.queue[0].severity, Severity::Error);
+
+        notifications.expire(Instant::now() + Duration::from_secs(7));
+        assert!(notifications.queue.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+#![allow(dead_code)] // Remove this once you start using the code
+
+use std::collections::VecDeque;
+
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::{prelude::*, widgets::*};
+use vte::{Params, Parser, Perform};
+
+use super::Component;
+use crate::action::Action;
+
+/// The number of lines that are kept, older lines are dropped.
+const MAX_LINES: usize = 10_000;
+
+/// Shows the output of a background job as a scrolling log, with its ANSI colors.
+///
+/// The pane follows the end of the log until it is scrolled up, and starts over whenever the job
+/// is run again. Its id is the id of the job, so a single pane can be assigned to a layout slot
+/// with the job id.
+#[derive(Debug, Default)]
+pub struct OutputPane {
+    job: String,
+    lines: VecDeque<Line<'static>>,
+    status: Status,
+    /// How many lines the pane is scrolled up from the end of the log.
+    scroll: usize,
+    /// The number of lines that fit in the pane when it was last drawn.
+    height: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    #[default]
+    Idle,
+    Running,
+    Exited(Option<i32>),
+}
+
+impl OutputPane {
+    pub fn new(job: impl Into<String>) -> Self {
+        Self {
+            job: job.into(),
+            ..Self::default()
+        }
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        let max = self.lines.len().saturating_sub(self.height);
+        self.scroll = (self.scroll + lines).min(max);
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+}
+
+impl Component for OutputPane {
+    fn id(&self) -> &str {
+        &self.job
+    }
+
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_up(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_down(1),
+            KeyCode::PageUp => self.scroll_up(self.height),
+            KeyCode::PageDown => self.scroll_down(self.height),
+            KeyCode::Home => self.scroll_up(self.lines.len()),
+            KeyCode::End => self.scroll = 0,
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn handle_mouse_event(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll_up(3),
+            MouseEventKind::ScrollDown => self.scroll_down(3),
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    /// The whole log counts as selected, without its colors.
+    fn selected_text(&self) -> Option<String> {
+        let lines: Vec<String> = self.lines.iter().map(Line::to_string).collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    fn update(&mut self, action: Action) -> Result<Option<Action>> {
+        match action {
+            Action::RunJob { id, .. } if id == self.job => {
+                self.lines.clear();
+                self.scroll = 0;
+                self.status = Status::Running;
+            }
+            Action::JobOutput { id, line, .. } if id == self.job => {
+                if self.lines.len() == MAX_LINES {
+                    self.lines.pop_front();
+                }
+                self.lines.push_back(parse_ansi(&line));
+                if self.scroll > 0 {
+                    // keep showing the same lines while scrolled up
+                    self.scroll_up(1);
+                }
+            }
+            Action::JobExited { id, code, .. } if id == self.job => {
+                self.status = Status::Exited(code)
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let status = match self.status {
+            Status::Idle => Line::default(),
+            Status::Running => Line::from("running").yellow(),
+            Status::Exited(Some(0)) => Line::from("exit 0").green(),
+            Status::Exited(Some(code)) => Line::from(format!("exit {code}")).red(),
+            Status::Exited(None) => Line::from("stopped").red(),
+        };
+        let block = Block::bordered()
+            .title(self.job.as_str())
+            .title(status.right_aligned());
+        let inner = block.inner(area);
+        self.height = inner.height.into();
+        let end = self.lines.len() - self.scroll.min(self.lines.len());
+        let start = end.saturating_sub(self.height);
+        let lines: Vec<Line> = self.lines.range(start..end).cloned().collect();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+        Ok(())
+    }
+}
+
+/// Turn text with ANSI escape sequences into a styled line, keeping the colors and text attributes
+/// and dropping every other escape sequence.
+pub fn parse_ansi(text: &str) -> Line<'static> {
+    let mut line = AnsiLine::default();
+    let mut parser = Parser::new();
+    for byte in text.bytes() {
+        parser.advance(&mut line, byte);
+    }
+    line.flush();
+    Line::from(line.spans)
+}
+
+#[derive(Default)]
+struct AnsiLine {
+    spans: Vec<Span<'static>>,
+    text: String,
+    style: Style,
+    width: usize,
+}
+
+impl AnsiLine {
+    fn flush(&mut self) {
+        if !self.text.is_empty() {
+            let text = std::mem::take(&mut self.text);
+            self.spans.push(Span::styled(text, self.style));
+        }
+    }
+
+    fn set_style(&mut self, style: Style) {
+        if style != self.style {
+            self.flush();
+            self.style = style;
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[u16]) {
+        let mut style = self.style;
+        let mut params = params.iter().copied();
+        while let Some(param) = params.next() {
+            style = match param {
+                0 => Style::default(),
+                1 => style.add_modifier(Modifier::BOLD),
+                2 => style.add_modifier(Modifier::DIM),
+                3 => style.add_modifier(Modifier::ITALIC),
+                4 => style.add_modifier(Modifier::UNDERLINED),
+                5 => style.add_modifier(Modifier::SLOW_BLINK),
+                6 => style.add_modifier(Modifier::RAPID_BLINK),
+                7 => style.add_modifier(Modifier::REVERSED),
+                8 => style.add_modifier(Modifier::HIDDEN),
+                9 => style.add_modifier(Modifier::CROSSED_OUT),
+                22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+                23 => style.remove_modifier(Modifier::ITALIC),
+                24 => style.remove_modifier(Modifier::UNDERLINED),
+                25 => style.remove_modifier(Modifier::SLOW_BLINK | Modifier::RAPID_BLINK),
+                27 => style.remove_modifier(Modifier::REVERSED),
+                28 => style.remove_modifier(Modifier::HIDDEN),
+                29 => style.remove_modifier(Modifier::CROSSED_OUT),
+                30..=37 => style.fg(ansi_color(param - 30)),
+                38 => match extended_color(&mut params) {
+                    Some(color) => style.fg(color),
+                    None => style,
+                },
+                39 => style.fg(Color::Reset),
+                40..=47 => style.bg(ansi_color(param - 40)),
+                48 => match extended_color(&mut params) {
+                    Some(color) => style.bg(color),
+                    None => style,
+                },
+                49 => style.bg(Color::Reset),
+                90..=97 => style.fg(ansi_color(param - 90 + 8)),
+                100..=107 => style.bg(ansi_color(param - 100 + 8)),
+                _ => style,
+            };
+        }
+        self.set_style(style);
+    }
+}
+
+impl Perform for AnsiLine {
+    fn print(&mut self, c: char) {
+        self.text.push(c);
+        self.width += 1;
+    }
+
+    fn execute(&mut self, byte: u8) {
+        if byte == b'\t' {
+            let spaces = 8 - self.width % 8;
+            self.text.push_str(&" ".repeat(spaces));
+            self.width += spaces;
+        }
+    }
+
+    fn csi_dispatch(&mut self, params: &Params, _intermediates: &[u8], ignore: bool, c: char) {
+        if c == 'm' && !ignore {
+            // parameters can be separated by `;` or `:`, e.g. `38;5;208` or `38:5:208`, and no
+            // parameters at all means a reset
+            let params: Vec<u16> = params.iter().flatten().copied().collect();
+            self.select_graphic_rendition(if params.is_empty() { &[0] } else { &params });
+        }
+    }
+}
+
+/// One of the 16 basic colors, with 8 to 15 being the bright variants.
+fn ansi_color(index: u16) -> Color {
+    match index {
+        0 => Color::Black,
+        1 => Color::Red,
+        2 => Color::Green,
+        3 => Color::Yellow,
+        4 => Color::Blue,
+        5 => Color::Magenta,
+        6 => Color::Cyan,
+        7 => Color::Gray,
+        8 => Color::DarkGray,
+        9 => Color::LightRed,
+        10 => Color::LightGreen,
+        11 => Color::LightYellow,
+        12 => Color::LightBlue,
+        13 => Color::LightMagenta,
+        14 => Color::LightCyan,
+        _ => Color::White,
+    }
+}
+
+/// The color after `38` or `48`: `5;<INDEX>` for the 256 color palette or `2;<R>;<G>;<B>`.
+fn extended_color(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut byte = || params.next().and_then(|param| u8::try_from(param).ok());
+    match byte()? {
+        5 => Some(Color::Indexed(byte()?)),
+        2 => Some(Color::Rgb(byte()?, byte()?, byte()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::jobs::Stream;
+
+    #[test]
+    fn test_parse_ansi() {
+        assert_eq!(
+            parse_ansi("\x1b[1;31merror\x1b[0m: \x1b[38;5;208mwarn\x1b[39m \x1b[48:2:1:2:3mrgb"),
+            Line::from(vec![
+                Span::styled("error", Style::new().red().bold()),
+                Span::raw(": "),
+                Span::styled("warn", Style::new().fg(Color::Indexed(208))),
+                Span::styled(" ", Style::new().fg(Color::Reset)),
+                Span::styled("rgb", Style::new().fg(Color::Reset).bg(Color::Rgb(1, 2, 3))),
+            ])
+        );
+        // other escape sequences, such as moving the cursor, are dropped
+        assert_eq!(parse_ansi("a\x1b[2Kb\tc"), Line::raw("ab      c"));
+        assert_eq!(
+            parse_ansi("\x1b[32mok\x1b[m"),
+            Line::from(Span::styled("ok", Style::new().green()))
+        );
+    }
+
+    fn lines(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        (0..3)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_follows_end_of_log() -> Result<()> {
+        let mut pane = OutputPane::new("build");
+        pane.update(Action::RunJob {
+            id: "build".into(),
+            command: vec!["make".into()],
+        })?;
+        for line in ["one", "two", "three"] {
+            pane.update(Action::JobOutput {
+                id: "build".into(),
+                run: 0,
+                stream: Stream::Stdout,
+                line: line.into(),
+            })?;
+        }
+        pane.update(Action::JobExited {
+            id: "build".into(),
+            run: 0,
+            code: Some(0),
+        })?;
+
+        let mut terminal = Terminal::new(TestBackend::new(14, 4))?;
+        terminal.draw(|frame| pane.draw(frame, frame.area()).unwrap())?;
+        assert_eq!(
+            lines(&terminal),
+            ["┌build─exit 0┐", "│two         │", "│three       │"]
+        );
+
+        pane.handle_key_event(KeyCode::Up.into())?;
+        terminal.draw(|frame| pane.draw(frame, frame.area()).unwrap())?;
+        assert_eq!(lines(&terminal)[1..], ["│one         │", "│two         │"]);
+        Ok(())
+    }
+}
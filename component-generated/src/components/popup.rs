@@ -0,0 +1,91 @@
+use color_eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::Component;
+use crate::action::Action;
+
+/// A message box or confirmation dialog that is shown as an overlay.
+#[derive(Debug, Clone)]
+pub struct Popup {
+    command_tx: Option<UnboundedSender<Action>>,
+    title: String,
+    message: String,
+    on_confirm: Option<Action>,
+}
+
+impl Popup {
+    pub fn message(title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            command_tx: None,
+            title: title.into(),
+            message: message.into(),
+            on_confirm: None,
+        }
+    }
+
+    pub fn confirm(
+        title: impl Into<String>,
+        message: impl Into<String>,
+        on_confirm: Action,
+    ) -> Self {
+        Self {
+            on_confirm: Some(on_confirm),
+            ..Self::message(title, message)
+        }
+    }
+
+    /// The height needed to show the whole message, the hint line and the borders.
+    pub fn height(&self) -> u16 {
+        let lines = self.message.lines().count().max(1) as u16;
+        lines + 4
+    }
+
+    fn hint(&self) -> &'static str {
+        if self.on_confirm.is_some() {
+            "[y]es  [n]o"
+        } else {
+            "[Enter] close"
+        }
+    }
+}
+
+impl Component for Popup {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+        self.command_tx = Some(tx);
+        Ok(())
+    }
+
+    fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let action = match (key.code, &self.on_confirm) {
+            (KeyCode::Char('y') | KeyCode::Enter, Some(on_confirm)) => {
+                if let Some(tx) = &self.command_tx {
+                    tx.send(on_confirm.clone())?;
+                }
+                Some(Action::PopOverlay)
+            }
+            (KeyCode::Char('n'), Some(_)) => Some(Action::PopOverlay),
+            (KeyCode::Enter, None) => Some(Action::PopOverlay),
+            _ => None,
+        };
+        Ok(action)
+    }
+
+    fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
+        let block = Block::bordered().title(self.title.as_str());
+        let [message, _, hint] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(block.inner(area));
+        frame.render_widget(block, area);
+        frame.render_widget(
+            Paragraph::new(self.message.as_str()).wrap(Wrap { trim: false }),
+            message,
+        );
+        frame.render_widget(Line::from(self.hint()).dim().right_aligned(), hint);
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+use std::{collections::HashMap, process::Stdio};
+
+use color_eyre::{eyre::eyre, Result};
+use futures::future;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::Command,
+    sync::mpsc::UnboundedSender,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::action::Action;
+
+/// Which output stream of a job a line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Runs commands in the background and reports their output as actions.
+///
+/// Every line that a job writes is sent as [`Action::JobOutput`], and [`Action::JobExited`] is sent
+/// once the job is done, including when it was cancelled. Each run of a job is numbered, so that
+/// the actions of a run that was replaced by running the same job again can be told apart, see
+/// [`Jobs::accept`].
+#[derive(Debug)]
+pub struct Jobs {
+    action_tx: UnboundedSender<Action>,
+    /// The current run of every job that hasn't exited yet, and the token that stops it.
+    running: HashMap<String, (u64, CancellationToken)>,
+    next_run: u64,
+}
+
+impl Jobs {
+    pub fn new(action_tx: UnboundedSender<Action>) -> Self {
+        Self {
+            action_tx,
+            running: HashMap::new(),
+            next_run: 0,
+        }
+    }
+
+    /// Start running `command` as the job `id`, replacing any job that is still running as `id`.
+    ///
+    /// If the command can't be started, the job exits straight away and an error is returned.
+    pub fn spawn(&mut self, id: &str, command: &[String]) -> Result<()> {
+        self.cancel(id);
+        let run = self.next_run;
+        self.next_run += 1;
+        let token = CancellationToken::new();
+        self.running.insert(id.to_string(), (run, token.clone()));
+        let spawned = command
+            .split_first()
+            .ok_or_else(|| eyre!("no command given for job {id}"))
+            .and_then(|(program, args)| {
+                Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|err| eyre!("failed to run {program}: {err}"))
+            });
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(err) => {
+                let id = id.to_string();
+                self.action_tx.send(Action::JobExited {
+                    id,
+                    run,
+                    code: None,
+                })?;
+                return Err(err);
+            }
+        };
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+
+        let action_tx = self.action_tx.clone();
+        let id = id.to_string();
+        tokio::spawn(async move {
+            let output = future::join(
+                forward(&id, run, Stream::Stdout, stdout, &action_tx),
+                forward(&id, run, Stream::Stderr, stderr, &action_tx),
+            );
+            let exited = tokio::select! {
+                _ = token.cancelled() => None,
+                (status, _) = future::join(child.wait(), output) => Some(status),
+            };
+            let code = match exited {
+                Some(status) => status.ok().and_then(|status| status.code()),
+                None => {
+                    let _ = child.kill().await;
+                    None
+                }
+            };
+            let _ = action_tx.send(Action::JobExited { id, run, code });
+        });
+        Ok(())
+    }
+
+    /// Stop the job `id`, if it is running. It still reports that it exited.
+    pub fn cancel(&mut self, id: &str) {
+        if let Some((_, token)) = self.running.get(id) {
+            token.cancel();
+        }
+    }
+
+    /// Stop every job that is running.
+    pub fn cancel_all(&mut self) {
+        for (_, token) in self.running.values() {
+            token.cancel();
+        }
+    }
+
+    /// Whether an action should be dispatched, which is not the case for the output and exit of
+    /// a run that was replaced by a newer run of the same job. Jobs that exit are forgotten.
+    pub fn accept(&mut self, action: &Action) -> bool {
+        match action {
+            Action::JobOutput { id, run, .. } => self.is_current(id, *run),
+            Action::JobExited { id, run, .. } if self.is_current(id, *run) => {
+                self.running.remove(id);
+                true
+            }
+            Action::JobExited { .. } => false,
+            _ => true,
+        }
+    }
+
+    fn is_current(&self, id: &str, run: u64) -> bool {
+        self.running
+            .get(id)
+            .is_some_and(|(current, _)| *current == run)
+    }
+}
+
+/// Send every line of `reader` as [`Action::JobOutput`], replacing invalid UTF-8.
+async fn forward(
+    id: &str,
+    run: u64,
+    stream: Stream,
+    reader: impl AsyncRead + Unpin,
+    action_tx: &UnboundedSender<Action>,
+) {
+    let mut lines = BufReader::new(reader).split(b'\n');
+    while let Ok(Some(line)) = lines.next_segment().await {
+        let line = String::from_utf8_lossy(&line);
+        let action = Action::JobOutput {
+            id: id.to_string(),
+            run,
+            stream,
+            line: line.trim_end_matches('\r').to_string(),
+        };
+        if action_tx.send(action).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_streams_output_and_exit_code() -> Result<()> {
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let mut jobs = Jobs::new(action_tx);
+        jobs.spawn(
+            "job",
+            &command(&["sh", "-c", "echo out; echo err >&2; exit 3"]),
+        )?;
+        let mut output = Vec::new();
+        while let Some(action) = action_rx.recv().await {
+            match action {
+                Action::JobOutput { stream, line, .. } => output.push((stream, line)),
+                Action::JobExited { id, code, .. } => {
+                    assert_eq!((id.as_str(), code), ("job", Some(3)));
+                    break;
+                }
+                action => panic!("unexpected action {action:?}"),
+            }
+        }
+        // the order of the two streams is not defined
+        output.sort_by_key(|(stream, _)| *stream == Stream::Stderr);
+        assert_eq!(
+            output,
+            [
+                (Stream::Stdout, "out".to_string()),
+                (Stream::Stderr, "err".to_string())
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cancel() -> Result<()> {
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let mut jobs = Jobs::new(action_tx);
+        jobs.spawn("sleep", &command(&["sleep", "10"]))?;
+        jobs.cancel("sleep");
+        assert_eq!(
+            action_rx.recv().await,
+            Some(Action::JobExited {
+                id: "sleep".into(),
+                run: 0,
+                code: None
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rerun_replaces_previous_run() -> Result<()> {
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        let mut jobs = Jobs::new(action_tx);
+        jobs.spawn("job", &command(&["sh", "-c", "echo old; sleep 10"]))?;
+        // wait until the first run has written its output
+        let first = action_rx.recv().await;
+        jobs.spawn("job", &command(&["echo", "new"]))?;
+        assert!(!jobs.accept(&first.expect("output of the first run")));
+
+        let mut accepted = Vec::new();
+        while let Some(action) = action_rx.recv().await {
+            if jobs.accept(&action) {
+                accepted.push(action.clone());
+            }
+            if matches!(action, Action::JobExited { run: 1, .. }) {
+                break;
+            }
+        }
+        assert_eq!(
+            accepted,
+            [
+                Action::JobOutput {
+                    id: "job".into(),
+                    run: 1,
+                    stream: Stream::Stdout,
+                    line: "new".into()
+                },
+                Action::JobExited {
+                    id: "job".into(),
+                    run: 1,
+                    code: Some(0)
+                },
+            ]
+        );
+        // the job is forgotten once it has exited
+        assert!(jobs.running.is_empty());
+        Ok(())
+    }
+}
@@ -6,11 +6,22 @@ use crate::app::App;
 
 mod action;
 mod app;
+mod capabilities;
 mod cli;
+mod clipboard;
 mod components;
 mod config;
+mod editor;
 mod errors;
+mod focus;
+mod gesture;
+mod jobs;
+mod layout;
 mod logging;
+mod overlay;
+mod router;
+mod session;
+mod signals;
 mod tui;
 
 #[tokio::main]
@@ -19,7 +30,15 @@ async fn main() -> Result<()> {
     crate::logging::init()?;
 
     let args = Cli::parse();
-    let mut app = App::new(args.tick_rate, args.frame_rate)?;
+    let mut app = App::new(args.tick_rate, args.frame_rate)?
+        .record(args.record)
+        .replay(args.replay)
+        .viewport(args.viewport)
+        .show_fps(args.show_fps)
+        .power_policy(args.power_policy)
+        .keyboard_enhancement(args.keyboard_enhancement)
+        .paste(args.paste)
+        .mouse(args.mouse);
     app.run().await?;
     Ok(())
 }
@@ -0,0 +1,111 @@
+#![allow(dead_code)] // Remove this once you start using the code
+
+use std::collections::HashMap;
+
+use crate::app::Mode;
+
+/// Tracks the current mode of the app, the modes that were navigated away from, and which
+/// components make up the screen of each mode.
+///
+/// Components are identified by their [`Component::id`](crate::components::Component::id).
+/// Components that are not part of any screen are active in every mode.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Router {
+    mode: Mode,
+    history: Vec<Mode>,
+    screens: HashMap<Mode, Vec<String>>,
+}
+
+impl Router {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Add the components with the given ids to the screen of a mode.
+    pub fn screen<I, S>(mut self, mode: Mode, component_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.screens
+            .entry(mode)
+            .or_default()
+            .extend(component_ids.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn history(&self) -> &[Mode] {
+        &self.history
+    }
+
+    /// Switch to a mode, remembering the current mode so that [`Router::back`] can return to it.
+    pub fn navigate(&mut self, mode: Mode) {
+        if mode != self.mode {
+            self.history.push(self.mode);
+            self.mode = mode;
+        }
+    }
+
+    /// Return to the previous mode. Returns false if there is no mode to go back to.
+    pub fn back(&mut self) -> bool {
+        match self.history.pop() {
+            Some(mode) => {
+                self.mode = mode;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a component is part of the current screen, or of no screen at all.
+    pub fn is_active(&self, component_id: &str) -> bool {
+        let on_screen = |ids: &Vec<String>| ids.iter().any(|id| id == component_id);
+        self.screens.get(&self.mode).is_some_and(on_screen) || !self.screens.values().any(on_screen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_components_without_screen_are_always_active() {
+        let router = Router::new(Mode::Home).screen(Mode::Home, ["Home"]);
+        assert!(router.is_active("Home"));
+        assert!(router.is_active("FpsCounter"));
+    }
+
+    #[test]
+    fn test_navigate_and_back() {
+        let mut router = Router::new(Mode::Home)
+            .screen(Mode::Home, ["Home"])
+            .screen(Mode::About, ["About"]);
+        router.navigate(Mode::About);
+        assert!(!router.is_active("Home"));
+        assert!(router.is_active("About"));
+        assert_eq!(router.history(), [Mode::Home]);
+
+        assert!(router.back());
+        assert!(router.is_active("Home"));
+        assert!(!router.is_active("About"));
+        assert_eq!(router.history(), []);
+    }
+
+    #[test]
+    fn test_navigate_to_current_mode_keeps_history() {
+        let mut router = Router::new(Mode::Home);
+        router.navigate(Mode::Home);
+        assert_eq!(router.mode(), Mode::Home);
+        assert_eq!(router.history(), []);
+        assert!(!router.back());
+    }
+}
@@ -1,15 +1,79 @@
 use serde::{Deserialize, Serialize};
 use strum::Display;
 
-#[derive(Debug, Clone, PartialEq, Eq, Display, Serialize, Deserialize)]
+use crate::{app::Mode, errors::ErrorDetails, jobs::Stream, overlay::Overlay};
+
+#[derive(Debug, Clone, PartialEq, Display, Serialize, Deserialize)]
 pub enum Action {
     Tick,
     Render,
+    RequestRender,
     Resize(u16, u16),
     Suspend,
     Resume,
     Quit,
     ClearScreen,
-    Error(String),
+    ReloadConfig,
+    Error(ErrorDetails),
     Help,
+    FocusNext,
+    FocusPrevious,
+    Focus(String),
+    PushOverlay(Overlay),
+    PopOverlay,
+    Navigate(Mode),
+    Back,
+    Notify(Severity, String),
+    SetTickRate(f64),
+    SetFrameRate(f64),
+    PauseTicks,
+    ResumeTicks,
+    /// Let the user edit `text` in their `$VISUAL` or `$EDITOR`, on behalf of the component `id`.
+    Edit {
+        id: String,
+        text: String,
+    },
+    /// The text that the user saved in their editor, for the component `id` that asked for it.
+    Edited {
+        id: String,
+        text: String,
+    },
+    /// Run `command`, e.g. `["cargo", "build"]`, in the background as the job `id`.
+    RunJob {
+        id: String,
+        command: Vec<String>,
+    },
+    /// A line that the job `id` wrote to `stream`, during its `run`.
+    JobOutput {
+        id: String,
+        run: u64,
+        stream: Stream,
+        line: String,
+    },
+    /// The `run` of the job `id` is done, with its exit code unless it was cancelled or killed by a
+    /// signal.
+    JobExited {
+        id: String,
+        run: u64,
+        code: Option<i32>,
+    },
+    CancelJob(String),
+    CancelJobs,
+    /// Put the text on the clipboard, or only in the app's own register if the terminal can't.
+    Copy(String),
+    /// Copy the text that is selected in the focused component.
+    CopySelection,
+    /// Paste the text that was last copied into the focused component.
+    Paste,
+}
+
+/// How important a message is, from least to most severe.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, Serialize, Deserialize,
+)]
+pub enum Severity {
+    #[default]
+    Info,
+    Warning,
+    Error,
 }
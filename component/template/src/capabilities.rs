@@ -0,0 +1,352 @@
+use std::{env, fmt, time::Duration};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// How long to wait for the terminal to answer queries.
+///
+/// Terminals answer within a few milliseconds, even over SSH, and startup only waits that long. A
+/// terminal that doesn't answer at all delays startup by the whole timeout, which is generous so
+/// that a slow answer isn't read as input after the tui has started.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The DEC private mode that reports focus changes.
+const FOCUS_EVENTS_MODE: u16 = 1004;
+
+/// The DEC private mode that holds back drawing until a frame is complete.
+const SYNCHRONIZED_OUTPUT_MODE: u16 = 2026;
+
+/// What the terminal supports, so that components can fall back to something simpler when a
+/// feature is missing, e.g. to the 256 color palette without truecolor or to plain text without
+/// hyperlinks.
+///
+/// This is detected when the tui is entered, first from environment variables and then, where
+/// the terminal can be asked, from its answers to queries.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// 24-bit RGB colors.
+    pub truecolor: bool,
+    /// The kitty keyboard protocol, see [`crate::tui::Features::keyboard_enhancement`].
+    pub keyboard_enhancement: bool,
+    /// Reporting when the terminal gains and loses focus.
+    pub focus_events: bool,
+    /// Synchronized output, which avoids tearing while a frame is drawn.
+    pub synchronized_output: bool,
+    /// OSC 8 hyperlinks.
+    pub hyperlinks: bool,
+    /// Copying to the clipboard with OSC 52, see [`crate::clipboard::osc52`].
+    pub clipboard: bool,
+}
+
+impl Capabilities {
+    /// Guess the capabilities from `COLORTERM`, `TERM`, `TERM_PROGRAM` and a few
+    /// terminal-specific variables.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let term_program = var("TERM_PROGRAM").unwrap_or_default();
+        let colorterm = var("COLORTERM").unwrap_or_default();
+        let kitty = term == "xterm-kitty";
+        let modern = kitty
+            || term == "xterm-ghostty"
+            || matches!(
+                term_program.as_str(),
+                "WezTerm" | "ghostty" | "iTerm.app" | "vscode"
+            )
+            || var("WT_SESSION").is_some();
+        let basic = matches!(term.as_str(), "" | "dumb" | "linux");
+        let vte_version = var("VTE_VERSION").and_then(|version| version.parse::<u32>().ok());
+        Self {
+            truecolor: matches!(colorterm.as_str(), "truecolor" | "24bit")
+                || term.ends_with("-direct")
+                || modern,
+            keyboard_enhancement: kitty || matches!(term_program.as_str(), "WezTerm" | "ghostty"),
+            focus_events: !basic,
+            synchronized_output: modern || term.starts_with("foot") || term == "alacritty",
+            hyperlinks: modern
+                || term.starts_with("foot")
+                || vte_version.is_some_and(|version| version >= 5000)
+                || var("KONSOLE_VERSION").is_some(),
+            clipboard: !basic && term_program != "Apple_Terminal",
+        }
+    }
+
+    /// Ask the terminal which of the modes it supports, and trust its answers over the guesses.
+    ///
+    /// This takes a round trip to the terminal, or [`QUERY_TIMEOUT`] if it doesn't answer. The
+    /// terminal must be in raw mode, and nothing else may be reading its input. Whatever is typed
+    /// while waiting for the answers is returned as key events, for the caller to handle as input.
+    pub fn query(mut self) -> (Self, Vec<KeyEvent>) {
+        let modes = [FOCUS_EVENTS_MODE, SYNCHRONIZED_OUTPUT_MODE];
+        let (replies, typed) = split_replies(&query_modes(&modes));
+        for (mode, supported) in parse_mode_reports(&replies) {
+            match mode {
+                FOCUS_EVENTS_MODE => self.focus_events = supported,
+                SYNCHRONIZED_OUTPUT_MODE => self.synchronized_output = supported,
+                _ => {}
+            }
+        }
+        (self, parse_keys(&typed))
+    }
+}
+
+impl fmt::Display for Capabilities {
+    /// The supported capabilities as a comma separated list.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = [
+            (self.truecolor, "truecolor"),
+            (self.keyboard_enhancement, "keyboard enhancement"),
+            (self.focus_events, "focus events"),
+            (self.synchronized_output, "synchronized output"),
+            (self.hyperlinks, "hyperlinks"),
+            (self.clipboard, "clipboard"),
+        ];
+        let supported: Vec<&str> = names
+            .into_iter()
+            .filter_map(|(supported, name)| supported.then_some(name))
+            .collect();
+        if supported.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", supported.join(", "))
+        }
+    }
+}
+
+/// Ask the terminal whether it supports the DEC private modes, with DECRQM, and return everything
+/// that it sent back.
+///
+/// Every terminal answers the primary device attributes query, so that is sent last to find out
+/// when the terminal is done answering, instead of always waiting for the timeout. The response
+/// also contains whatever was typed in the meantime.
+#[cfg(unix)]
+fn query_modes(modes: &[u16]) -> String {
+    use std::{
+        io::{stdout, IsTerminal, Write},
+        time::Instant,
+    };
+
+    if !std::io::stdin().is_terminal() {
+        return String::new();
+    }
+    let mut request: String = modes.iter().map(|mode| format!("\x1b[?{mode}$p")).collect();
+    request.push_str("\x1b[c");
+    let mut stdout = stdout();
+    if stdout
+        .write_all(request.as_bytes())
+        .and_then(|()| stdout.flush())
+        .is_err()
+    {
+        return String::new();
+    }
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut response = Vec::new();
+    while !is_complete(&String::from_utf8_lossy(&response)) {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let mut fd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a valid pollfd for the duration of the call
+        let ready = unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            break;
+        }
+        let mut buffer = [0u8; 256];
+        // SAFETY: `buffer` is valid for writes of its length
+        let read =
+            unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len()) };
+        let Ok(read @ 1..) = usize::try_from(read) else {
+            break;
+        };
+        response.extend_from_slice(&buffer[..read]);
+    }
+    String::from_utf8_lossy(&response).into_owned()
+}
+
+#[cfg(not(unix))]
+fn query_modes(_modes: &[u16]) -> String {
+    String::new()
+}
+
+/// Split a response into the replies to queries, which start with `ESC [ ?` and end with `$ y` or
+/// `c`, and the input that was typed in between them.
+fn split_replies(response: &str) -> (String, String) {
+    let mut replies = String::new();
+    let mut typed = String::new();
+    let mut rest = response;
+    while let Some(start) = rest.find("\x1b[?") {
+        typed.push_str(&rest[..start]);
+        let report = &rest[start + 3..];
+        let params = report.trim_start_matches(|c: char| c.is_ascii_digit() || c == ';');
+        let end = ["$y", "c"]
+            .into_iter()
+            .find(|terminator| params.starts_with(terminator))
+            .map(|terminator| report.len() - params.len() + terminator.len());
+        match end {
+            Some(end) => {
+                replies.push_str(&rest[start..start + 3 + end]);
+                rest = &report[end..];
+            }
+            None => {
+                typed.push_str(&rest[start..start + 3]);
+                rest = report;
+            }
+        }
+    }
+    typed.push_str(rest);
+    (replies, typed)
+}
+
+/// The keys in input that was typed while the terminal was queried.
+///
+/// This only tells apart characters, `Enter`, `Tab`, `Backspace`, `Esc` and `Ctrl` with a letter,
+/// which covers what is typed while an app starts. Other escape sequences, e.g. for arrow keys,
+/// are dropped.
+fn parse_keys(input: &str) -> Vec<KeyEvent> {
+    let mut keys = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' => match chars.peek() {
+                Some('[') => {
+                    // a CSI sequence ends with a byte in the range `@` to `~`
+                    chars.next();
+                    chars.find(|c| ('@'..='~').contains(c));
+                    continue;
+                }
+                Some('O') => {
+                    chars.next();
+                    chars.next();
+                    continue;
+                }
+                _ => KeyEvent::from(KeyCode::Esc),
+            },
+            '\r' | '\n' => KeyEvent::from(KeyCode::Enter),
+            '\t' => KeyEvent::from(KeyCode::Tab),
+            '\x7f' | '\x08' => KeyEvent::from(KeyCode::Backspace),
+            '\x01'..='\x1a' => {
+                let letter = char::from(c as u8 - 1 + b'a');
+                KeyEvent::new(KeyCode::Char(letter), KeyModifiers::CONTROL)
+            }
+            c if c.is_control() => continue,
+            c if c.is_uppercase() => KeyEvent::new(KeyCode::Char(c), KeyModifiers::SHIFT),
+            c => KeyEvent::from(KeyCode::Char(c)),
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// Whether the response contains the answer to the primary device attributes query, e.g.
+/// `ESC [ ? 62 ; 22 c`.
+fn is_complete(response: &str) -> bool {
+    response.split("\x1b[?").skip(1).any(|report| {
+        let params = report.trim_start_matches(|c: char| c.is_ascii_digit() || c == ';');
+        params.starts_with('c')
+    })
+}
+
+/// The modes in DECRQM reports such as `ESC [ ? 2026 ; 2 $ y`, and whether they are supported.
+///
+/// The state is 0 if the mode is not recognized, 1 or 2 if it is set or reset, and 3 or 4 if it is
+/// permanently set or reset.
+fn parse_mode_reports(response: &str) -> Vec<(u16, bool)> {
+    response
+        .split("\x1b[?")
+        .skip(1)
+        .filter_map(|report| {
+            let (mode, rest) = report.split_once(';')?;
+            let (state, _) = rest.split_once("$y")?;
+            let supported = matches!(state.parse::<u8>().ok()?, 1..=3);
+            Some((mode.parse().ok()?, supported))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn from_vars<const N: usize>(vars: [(&str, &str); N]) -> Capabilities {
+        let vars = HashMap::from(vars);
+        Capabilities::from_vars(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn test_from_vars() {
+        assert_eq!(from_vars([]), Capabilities::default());
+        assert_eq!(from_vars([("TERM", "dumb")]), Capabilities::default());
+        assert_eq!(
+            from_vars([("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]),
+            Capabilities {
+                truecolor: true,
+                focus_events: true,
+                clipboard: true,
+                ..Capabilities::default()
+            }
+        );
+        assert_eq!(
+            from_vars([("TERM", "xterm-kitty")]),
+            Capabilities {
+                truecolor: true,
+                keyboard_enhancement: true,
+                focus_events: true,
+                synchronized_output: true,
+                hyperlinks: true,
+                clipboard: true,
+            }
+        );
+        assert!(
+            !from_vars([
+                ("TERM", "xterm-256color"),
+                ("TERM_PROGRAM", "Apple_Terminal")
+            ])
+            .clipboard
+        );
+    }
+
+    #[test]
+    fn test_parse_mode_reports() {
+        let response = "\x1b[?1004;2$y\x1b[?2026;0$y\x1b[?62;22c";
+        assert!(is_complete(response));
+        assert!(!is_complete("\x1b[?1004;2$y"));
+        assert_eq!(parse_mode_reports(response), [(1004, true), (2026, false)]);
+    }
+
+    #[test]
+    fn test_typed_input_is_kept() {
+        let response = "q\x1b[?1004;2$yX\x1b[?62;22c\r\x1b[A\x03";
+        let (replies, typed) = split_replies(response);
+        assert_eq!(replies, "\x1b[?1004;2$y\x1b[?62;22c");
+        assert_eq!(typed, "qX\r\x1b[A\x03");
+        assert_eq!(
+            parse_keys(&typed),
+            [
+                KeyEvent::from(KeyCode::Char('q')),
+                KeyEvent::new(KeyCode::Char('X'), KeyModifiers::SHIFT),
+                KeyEvent::from(KeyCode::Enter),
+                KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Capabilities::default().to_string(), "none");
+        let capabilities = Capabilities {
+            truecolor: true,
+            hyperlinks: true,
+            ..Capabilities::default()
+        };
+        assert_eq!(capabilities.to_string(), "truecolor, hyperlinks");
+    }
+}
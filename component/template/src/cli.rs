@@ -1,6 +1,13 @@
+use std::path::PathBuf;
+
 use clap::Parser;
+use ratatui::{layout::Rect, Viewport};
 
-use crate::config::{get_config_dir, get_data_dir};
+use crate::{
+    app::PowerPolicy,
+    capabilities::Capabilities,
+    config::{get_config_dir, get_data_dir},
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
@@ -12,6 +19,60 @@ pub struct Cli {
     /// Frame rate, i.e. number of frames per second
     #[arg(short, long, value_name = "FLOAT", default_value_t = 60.0)]
     pub frame_rate: f64,
+
+    /// Record every event and action to a JSON lines file
+    #[arg(long, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Replay the events of a recorded session instead of reading the terminal
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Where to draw: `fullscreen`, `inline:<HEIGHT>` or `fixed:<X>,<Y>,<WIDTH>,<HEIGHT>`
+    #[arg(
+        long,
+        value_name = "VIEWPORT",
+        default_value = "fullscreen",
+        value_parser = parse_viewport
+    )]
+    pub viewport: Viewport,
+
+    /// What to do with ticks and frames while the terminal is unfocused
+    #[arg(long, value_enum, default_value_t = PowerPolicy::Throttle)]
+    pub power_policy: PowerPolicy,
+
+    /// Enable mouse support
+    #[arg(long)]
+    pub mouse: bool,
+
+    /// Deliver pasted text as a single event
+    #[arg(long)]
+    pub paste: bool,
+
+    /// Use the kitty keyboard protocol if the terminal supports it
+    #[arg(long)]
+    pub keyboard_enhancement: bool,
+
+    /// Show the tick and frame rates, which draws every frame
+    #[arg(long)]
+    pub show_fps: bool,
+}
+
+fn parse_viewport(s: &str) -> Result<Viewport, String> {
+    let (kind, args) = s.split_once(':').unwrap_or((s, ""));
+    let numbers = args
+        .split(',')
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| arg.trim().parse::<u16>().map_err(|e| format!("{arg}: {e}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    match (kind, numbers.as_slice()) {
+        ("fullscreen", []) => Ok(Viewport::Fullscreen),
+        ("inline", &[height]) => Ok(Viewport::Inline(height)),
+        ("fixed", &[x, y, width, height]) => Ok(Viewport::Fixed(Rect::new(x, y, width, height))),
+        _ => Err(format!(
+            "expected fullscreen, inline:<HEIGHT> or fixed:<X>,<Y>,<WIDTH>,<HEIGHT>, got {s}"
+        )),
+    }
 }
 
 const VERSION_MESSAGE: &str = concat!(
@@ -29,6 +90,7 @@ pub fn version() -> String {
     // let current_exe_path = PathBuf::from(clap::crate_name!()).display().to_string();
     let config_dir_path = get_config_dir().display().to_string();
     let data_dir_path = get_data_dir().display().to_string();
+    let capabilities = Capabilities::from_env();
 
     format!(
         "\
@@ -37,6 +99,26 @@ pub fn version() -> String {
 Authors: {author}
 
 Config directory: {config_dir_path}
-Data directory: {data_dir_path}"
+Data directory: {data_dir_path}
+Terminal (from the environment): {capabilities}"
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_viewport() {
+        assert_eq!(parse_viewport("fullscreen"), Ok(Viewport::Fullscreen));
+        assert_eq!(parse_viewport("inline:8"), Ok(Viewport::Inline(8)));
+        assert_eq!(
+            parse_viewport("fixed:1,2,30,10"),
+            Ok(Viewport::Fixed(Rect::new(1, 2, 30, 10)))
+        );
+        assert!(parse_viewport("inline").is_err());
+        assert!(parse_viewport("fixed:1,2").is_err());
+    }
+}
@@ -1,21 +1,47 @@
 use color_eyre::Result;
-use crossterm::event::{KeyEvent, MouseEvent};
+use crossterm::event::{KeyEvent, KeyEventKind, MouseEvent};
+use futures::{
+    future::{self, LocalBoxFuture},
+    FutureExt,
+};
 use ratatui::{
-    layout::{Rect, Size},
+    layout::{Position, Rect, Size},
     Frame,
 };
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::{sync::mpsc::UnboundedSender, time::Instant};
+use tracing::warn;
 
-use crate::{action::Action, config::Config, tui::Event};
+use crate::{
+    action::Action, capabilities::Capabilities, config::Config, gesture::Gesture, tui::Event,
+};
 
+pub mod about;
 pub mod fps;
+pub mod help;
 pub mod home;
+pub mod notifications;
+pub mod output;
+pub mod popup;
 
 /// `Component` is a trait that represents a visual and interactive element of the user interface.
 ///
 /// Implementors of this trait can be registered with the main application loop and will be able to
 /// receive events, update state, and be rendered on the screen.
 pub trait Component {
+    /// A unique identifier for the component.
+    ///
+    /// The app uses this to track which component has focus, and to route mouse events, screens
+    /// and layout slots. Defaults to the name of the type, so override this when more than one
+    /// instance of a component is in the tree, as the app fails with an error when two components
+    /// on a screen have the same id.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The identifier of the component.
+    fn id(&self) -> &str {
+        let type_name = std::any::type_name::<Self>();
+        type_name.rsplit("::").next().unwrap_or(type_name)
+    }
     /// Register an action handler that can send actions for processing if necessary.
     ///
     /// # Arguments
@@ -47,16 +73,84 @@ pub trait Component {
     /// # Arguments
     ///
     /// * `area` - Rectangular area to initialize the component within.
+    /// * `capabilities` - What the terminal supports, to fall back to something simpler when a
+    ///   feature is missing.
     ///
     /// # Returns
     ///
     /// * `Result<()>` - An Ok result or an error.
-    fn init(&mut self, area: Size) -> Result<()> {
-        let _ = area; // to appease clippy
+    fn init(&mut self, area: Size, capabilities: Capabilities) -> Result<()> {
+        let _ = (area, capabilities); // to appease clippy
         Ok(())
     }
+    /// Clean up before the app exits, e.g. save state or wait for background tasks to finish.
+    ///
+    /// This is called once the app is shutting down, after the event loop has stopped and before
+    /// the terminal is restored. Whatever is still running at the deadline is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - When the app exits, whether or not the teardown has finished.
+    ///
+    /// # Returns
+    ///
+    /// * `LocalBoxFuture<Result<()>>` - A future that completes once the component is torn down.
+    fn teardown(&mut self, deadline: Instant) -> LocalBoxFuture<'_, Result<()>> {
+        let _ = deadline; // to appease clippy
+        future::ok(()).boxed_local()
+    }
+    /// Whether the component can receive focus.
+    ///
+    /// Key events are only delivered to the focused component, and focus cycles through the
+    /// focusable components of the tree in depth-first order.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the component is part of the focus ring.
+    fn is_focusable(&self) -> bool {
+        false
+    }
+    /// Called when the component gains focus.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn on_focus(&mut self) -> Result<Option<Action>> {
+        Ok(None)
+    }
+    /// Called when the component loses focus.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn on_blur(&mut self) -> Result<Option<Action>> {
+        Ok(None)
+    }
+    /// Called when the terminal loses focus, e.g. when the user switches to another window.
+    ///
+    /// Depending on the power policy of the app, ticks and frames are throttled or paused until
+    /// the terminal is focused again, so this is the place to pause any polling work.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn on_terminal_focus_lost(&mut self) -> Result<Option<Action>> {
+        Ok(None)
+    }
+    /// Called when the terminal gains focus again after it was lost.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn on_terminal_focus_gained(&mut self) -> Result<Option<Action>> {
+        Ok(None)
+    }
     /// Handle incoming events and produce actions if necessary.
     ///
+    /// By default this dispatches key, mouse and paste events to the matching handlers. Changes of
+    /// the terminal focus are delivered through `on_terminal_focus_lost` and
+    /// `on_terminal_focus_gained` instead.
+    ///
     /// # Arguments
     ///
     /// * `event` - An optional event to be processed.
@@ -66,13 +160,20 @@ pub trait Component {
     /// * `Result<Option<Action>>` - An action to be processed or none.
     fn handle_events(&mut self, event: Option<Event>) -> Result<Option<Action>> {
         let action = match event {
+            Some(Event::Key(key_event)) if key_event.kind == KeyEventKind::Release => {
+                self.handle_key_release_event(key_event)?
+            }
             Some(Event::Key(key_event)) => self.handle_key_event(key_event)?,
             Some(Event::Mouse(mouse_event)) => self.handle_mouse_event(mouse_event)?,
+            Some(Event::Paste(text)) => self.handle_paste_event(text)?,
             _ => None,
         };
         Ok(action)
     }
-    /// Handle key events and produce actions if necessary.
+    /// Handle key press and repeat events and produce actions if necessary.
+    ///
+    /// Repeat events are only reported separately from presses when keyboard enhancement is
+    /// enabled; `key.kind` tells them apart.
     ///
     /// # Arguments
     ///
@@ -85,8 +186,30 @@ pub trait Component {
         let _ = key; // to appease clippy
         Ok(None)
     }
+    /// Handle key release events and produce actions if necessary.
+    ///
+    /// Terminals only report key releases when keyboard enhancement is enabled, which makes
+    /// hold-to-act interactions possible.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - A key release event to be processed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn handle_key_release_event(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+        let _ = key; // to appease clippy
+        Ok(None)
+    }
     /// Handle mouse events and produce actions if necessary.
     ///
+    /// Events are in the component's own coordinates. While a button is held down, the events keep
+    /// going to the component where it was pressed, also when the pointer leaves it. Coordinates
+    /// can't be negative, so left of or above the component they are 0; use the `delta` of the drag
+    /// gesture that comes with each of these events, see `handle_gesture_event`, to follow the
+    /// pointer there.
+    ///
     /// # Arguments
     ///
     /// * `mouse` - A mouse event to be processed.
@@ -98,6 +221,49 @@ pub trait Component {
         let _ = mouse; // to appease clippy
         Ok(None)
     }
+    /// Handle mouse gestures and produce actions if necessary.
+    ///
+    /// Gestures are recognized from the raw mouse events, which are still delivered to
+    /// `handle_mouse_event` first. Clicks report how many times the button was pressed in quick
+    /// succession, drags keep going to the component where they started, and hover events are sent
+    /// when the pointer moves onto or off the component.
+    ///
+    /// # Arguments
+    ///
+    /// * `gesture` - A gesture, in the component's own coordinates.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn handle_gesture_event(&mut self, gesture: Gesture) -> Result<Option<Action>> {
+        let _ = gesture; // to appease clippy
+        Ok(None)
+    }
+    /// Handle pasted text and produce actions if necessary.
+    ///
+    /// With bracketed paste enabled, pasted text arrives as a single event, including any line
+    /// breaks, instead of as one key event per character. Like key events, it is only delivered to
+    /// the focused component.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The pasted text.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<Action>>` - An action to be processed or none.
+    fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+        let _ = text; // to appease clippy
+        Ok(None)
+    }
+    /// The text that is selected in the component, which [`Action::CopySelection`] copies.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - The selected text, or none if nothing is selected.
+    fn selected_text(&self) -> Option<String> {
+        None
+    }
     /// Update the state of the component based on a received action. (REQUIRED)
     ///
     /// # Arguments
@@ -111,6 +277,66 @@ pub trait Component {
         let _ = action; // to appease clippy
         Ok(None)
     }
+    /// Whether the component has changed in a way that the app doesn't know about.
+    ///
+    /// The app only draws a frame when something changed: after an input event, after any action
+    /// other than `Tick` and `Render`, or when a component asks for it. Components that change on
+    /// their own, e.g. on `Tick`, can either return `true` here until they are drawn, or return
+    /// `Action::RequestRender` from `update`.
+    ///
+    /// While nothing needs to be drawn, the event loop stops sending `Render` until the next event
+    /// or action, so this is only checked after those.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the component needs to be drawn again.
+    fn needs_render(&self) -> bool {
+        false
+    }
+    /// Get the child components of this component, if any.
+    ///
+    /// The app walks the component tree recursively: every child returned here is registered and
+    /// initialized along with its parent, receives events and actions after its parent, and is
+    /// drawn on top of its parent. A parent can filter its children by only returning the ones
+    /// that are currently active.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<&mut dyn Component>` - The active child components.
+    fn children(&mut self) -> Vec<&mut dyn Component> {
+        Vec::new()
+    }
+    /// Lay out the child components within the area of this component.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - The area in which this component is drawn.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Rect>` - One area per child returned by `children`, in the same order. Children
+    ///   without a matching area are drawn in the area of their parent.
+    fn layout_children(&mut self, area: Rect) -> Vec<Rect> {
+        let _ = area; // to appease clippy
+        Vec::new()
+    }
+    /// Whether the component takes mouse events at a position within the area it was drawn in.
+    ///
+    /// Mouse events go to the topmost component under the pointer. Components that only draw
+    /// over part of their area, such as popups or toasts, can return `false` elsewhere to let the
+    /// pointer through to the components below.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position of the pointer on the screen.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the component is hit at that position.
+    fn hit_test(&self, position: Position) -> bool {
+        let _ = position; // to appease clippy
+        true
+    }
     /// Render the component on the screen. (REQUIRED)
     ///
     /// # Arguments
@@ -123,3 +349,147 @@ pub trait Component {
     /// * `Result<()>` - An Ok result or an error.
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()>;
 }
+
+/// Visit a component and all of its descendants depth-first, parents before their children.
+///
+/// # Arguments
+///
+/// * `component` - The root of the component tree to walk.
+/// * `visit` - A function that is called once for every component in the tree.
+///
+/// # Returns
+///
+/// * `Result<()>` - An Ok result or the first error returned by `visit`.
+pub fn walk(
+    component: &mut dyn Component,
+    visit: &mut impl FnMut(&mut dyn Component) -> Result<()>,
+) -> Result<()> {
+    visit(component)?;
+    for child in component.children() {
+        walk(child, visit)?;
+    }
+    Ok(())
+}
+
+/// Tear down a component and all of its descendants, children before their parents, giving up on
+/// any component that is not done by the deadline.
+///
+/// # Arguments
+///
+/// * `component` - The root of the component tree to tear down.
+/// * `deadline` - When to stop waiting for the components.
+///
+/// # Returns
+///
+/// * `LocalBoxFuture<Result<()>>` - A future that completes once the tree is torn down.
+pub fn teardown(
+    component: &mut dyn Component,
+    deadline: Instant,
+) -> LocalBoxFuture<'_, Result<()>> {
+    async move {
+        for child in component.children() {
+            teardown(child, deadline).await?;
+        }
+        let id = component.id().to_string();
+        match tokio::time::timeout_at(deadline, component.teardown(deadline)).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!("{id} did not tear down in time");
+                Ok(())
+            }
+        }
+    }
+    .boxed_local()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    type Log = Rc<RefCell<Vec<&'static str>>>;
+
+    struct Panel {
+        name: &'static str,
+        log: Log,
+        children: Vec<Box<dyn Component>>,
+    }
+
+    impl Panel {
+        fn new(name: &'static str, log: &Log, children: Vec<Box<dyn Component>>) -> Self {
+            let log = log.clone();
+            Self {
+                name,
+                log,
+                children,
+            }
+        }
+    }
+
+    impl Component for Panel {
+        fn children(&mut self) -> Vec<&mut dyn Component> {
+            self.children
+                .iter_mut()
+                .map(|child| child.as_mut() as &mut dyn Component)
+                .collect()
+        }
+
+        fn update(&mut self, _action: Action) -> Result<Option<Action>> {
+            self.log.borrow_mut().push(self.name);
+            Ok(None)
+        }
+
+        fn teardown(&mut self, _deadline: Instant) -> LocalBoxFuture<'_, Result<()>> {
+            self.log.borrow_mut().push(self.name);
+            future::ok(()).boxed_local()
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A component that never finishes tearing down.
+    struct Stuck;
+
+    impl Component for Stuck {
+        fn teardown(&mut self, _deadline: Instant) -> LocalBoxFuture<'_, Result<()>> {
+            future::pending().boxed_local()
+        }
+
+        fn draw(&mut self, _frame: &mut Frame, _area: Rect) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_walk_visits_parents_before_children() -> Result<()> {
+        let log = Log::default();
+        let nested = Panel::new("nested", &log, vec![]);
+        let left = Panel::new("left", &log, vec![Box::new(nested)]);
+        let right = Panel::new("right", &log, vec![]);
+        let mut root = Panel::new("root", &log, vec![Box::new(left), Box::new(right)]);
+        walk(&mut root, &mut |component| {
+            component.update(Action::Tick)?;
+            Ok(())
+        })?;
+        assert_eq!(*log.borrow(), ["root", "left", "nested", "right"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_teardown_children_first_until_deadline() -> Result<()> {
+        let log = Log::default();
+        let nested = Panel::new("nested", &log, vec![]);
+        let left = Panel::new("left", &log, vec![Box::new(nested), Box::new(Stuck)]);
+        let right = Panel::new("right", &log, vec![]);
+        let mut root = Panel::new("root", &log, vec![Box::new(left), Box::new(right)]);
+        let deadline = Instant::now() + std::time::Duration::from_millis(10);
+        teardown(&mut root, deadline).await?;
+        assert_eq!(*log.borrow(), ["nested", "left", "right", "root"]);
+        Ok(())
+    }
+}
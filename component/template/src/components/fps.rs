@@ -2,7 +2,7 @@ use std::time::Instant;
 
 use color_eyre::Result;
 use ratatui::{
-    layout::{Constraint, Layout, Rect},
+    layout::Rect,
     style::{Style, Stylize},
     text::Span,
     widgets::Paragraph,
@@ -77,15 +77,38 @@ impl Component for FpsCounter {
         Ok(None)
     }
 
+    /// Draw every frame, so that the counter shows the maximum frame rate rather than only the
+    /// frames that the rest of the app asked for.
+    fn needs_render(&self) -> bool {
+        true
+    }
+
     fn draw(&mut self, frame: &mut Frame, area: Rect) -> Result<()> {
-        let [top, _] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(area);
         let message = format!(
             "{:.2} ticks/sec, {:.2} FPS",
             self.ticks_per_second, self.frames_per_second
         );
         let span = Span::styled(message, Style::new().dim());
         let paragraph = Paragraph::new(span).right_aligned();
-        frame.render_widget(paragraph, top);
+        frame.render_widget(paragraph, area);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::app::harness::Harness;
+
+    #[tokio::test]
+    async fn test_renders_rates_right_aligned() -> Result<()> {
+        let mut harness =
+            Harness::with_components(vec![Box::new(FpsCounter::new())], 30, 2).await?;
+        harness.tick().await?.render().await?;
+        assert_eq!(harness.line(0), "      0.00 ticks/sec, 0.00 FPS");
+        assert_eq!(harness.take_actions(), [Action::Tick, Action::Render]);
         Ok(())
     }
 }
@@ -28,6 +28,10 @@ impl Component for Home {
         Ok(())
     }
 
+    fn is_focusable(&self) -> bool {
+        true
+    }
+
     fn update(&mut self, action: Action) -> Result<Option<Action>> {
         match action {
             Action::Tick => {
@@ -46,3 +50,61 @@ impl Component for Home {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{app::harness::Harness, tui::Event};
+
+    #[tokio::test]
+    async fn test_renders_in_body() -> Result<()> {
+        let mut harness = Harness::new(40, 3).await?;
+        harness.render().await?;
+        assert_eq!(harness.line(1), format!("{:40}", "hello world"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_keybinding_opens_help() -> Result<()> {
+        let mut harness = Harness::new(60, 20).await?;
+        harness.key(KeyCode::Char('?')).await?.render().await?;
+        assert_eq!(harness.take_actions(), [Action::Help, Action::Render]);
+        assert!(harness.line(2).contains("Help (Home)"));
+
+        harness.key(KeyCode::Esc).await?.render().await?;
+        assert_eq!(harness.take_actions(), [Action::PopOverlay, Action::Render]);
+        assert!(!harness.line(2).contains("Help (Home)"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scripted_quit() -> Result<()> {
+        let mut harness = Harness::new(40, 3).await?;
+        harness
+            .events([
+                Event::Tick,
+                Event::Key(KeyCode::Char('q').into()),
+                Event::Render,
+            ])
+            .await?;
+        // the app stops handling events once it quits
+        assert_eq!(harness.take_actions(), [Action::Tick, Action::Quit]);
+        assert!(harness.should_quit());
+        assert_eq!(harness.tui().source.exited, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_renders_only_when_dirty() -> Result<()> {
+        let mut harness = Harness::new(40, 3).await?;
+        harness.render().await?.tick().await?.render().await?;
+        assert_eq!(harness.take_actions(), [Action::Render, Action::Tick]);
+
+        harness.key(KeyCode::Tab).await?.render().await?;
+        assert_eq!(harness.take_actions(), [Action::FocusNext, Action::Render]);
+        Ok(())
+    }
+}
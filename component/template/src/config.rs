@@ -21,6 +21,9 @@ pub struct AppConfig {
     pub data_dir: PathBuf,
     #[serde(default)]
     pub config_dir: PathBuf,
+    /// Whether to enable bracketed paste.
+    #[serde(default)]
+    pub paste: bool,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -31,6 +34,8 @@ pub struct Config {
     pub keybindings: KeyBindings,
     #[serde(default)]
     pub styles: Styles,
+    #[serde(default)]
+    pub descriptions: HashMap<String, String>,
 }
 
 lazy_static! {
@@ -91,6 +96,11 @@ impl Config {
                 user_styles.entry(style_key.clone()).or_insert(*style);
             }
         }
+        for (action, description) in default_config.descriptions.iter() {
+            cfg.descriptions
+                .entry(action.clone())
+                .or_insert_with(|| description.clone());
+        }
 
         Ok(cfg)
     }
@@ -171,6 +181,19 @@ fn extract_modifiers(raw: &str) -> (&str, KeyModifiers) {
                 modifiers.insert(KeyModifiers::SHIFT);
                 current = &rest[6..];
             }
+            // these modifiers are only reported with keyboard enhancement enabled
+            rest if rest.starts_with("super-") => {
+                modifiers.insert(KeyModifiers::SUPER);
+                current = &rest[6..];
+            }
+            rest if rest.starts_with("hyper-") => {
+                modifiers.insert(KeyModifiers::HYPER);
+                current = &rest[6..];
+            }
+            rest if rest.starts_with("meta-") => {
+                modifiers.insert(KeyModifiers::META);
+                current = &rest[5..];
+            }
             _ => break, // break out of the loop if no known prefix is detected
         };
     }
@@ -200,22 +223,21 @@ fn parse_key_code_with_modifiers(
         "backspace" => KeyCode::Backspace,
         "delete" => KeyCode::Delete,
         "insert" => KeyCode::Insert,
-        "f1" => KeyCode::F(1),
-        "f2" => KeyCode::F(2),
-        "f3" => KeyCode::F(3),
-        "f4" => KeyCode::F(4),
-        "f5" => KeyCode::F(5),
-        "f6" => KeyCode::F(6),
-        "f7" => KeyCode::F(7),
-        "f8" => KeyCode::F(8),
-        "f9" => KeyCode::F(9),
-        "f10" => KeyCode::F(10),
-        "f11" => KeyCode::F(11),
-        "f12" => KeyCode::F(12),
         "space" => KeyCode::Char(' '),
         "hyphen" => KeyCode::Char('-'),
         "minus" => KeyCode::Char('-'),
         "tab" => KeyCode::Tab,
+        // these keys are only reported with keyboard enhancement enabled
+        "capslock" => KeyCode::CapsLock,
+        "scrolllock" => KeyCode::ScrollLock,
+        "numlock" => KeyCode::NumLock,
+        "printscreen" => KeyCode::PrintScreen,
+        "pause" => KeyCode::Pause,
+        "menu" => KeyCode::Menu,
+        "keypadbegin" => KeyCode::KeypadBegin,
+        f if f.starts_with('f') && f[1..].parse::<u8>().is_ok_and(|n| (1..=35).contains(&n)) => {
+            KeyCode::F(f[1..].parse().unwrap())
+        }
         c if c.len() == 1 => {
             let mut c = c.chars().next().unwrap();
             if modifiers.contains(KeyModifiers::SHIFT) {
@@ -246,7 +268,7 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
         KeyCode::Delete => "delete",
         KeyCode::Insert => "insert",
         KeyCode::F(c) => {
-            char = format!("f({c})");
+            char = format!("f{c}");
             &char
         }
         KeyCode::Char(' ') => "space",
@@ -256,18 +278,18 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
         }
         KeyCode::Esc => "esc",
         KeyCode::Null => "",
-        KeyCode::CapsLock => "",
-        KeyCode::Menu => "",
-        KeyCode::ScrollLock => "",
+        KeyCode::CapsLock => "capslock",
+        KeyCode::Menu => "menu",
+        KeyCode::ScrollLock => "scrolllock",
         KeyCode::Media(_) => "",
-        KeyCode::NumLock => "",
-        KeyCode::PrintScreen => "",
-        KeyCode::Pause => "",
-        KeyCode::KeypadBegin => "",
+        KeyCode::NumLock => "numlock",
+        KeyCode::PrintScreen => "printscreen",
+        KeyCode::Pause => "pause",
+        KeyCode::KeypadBegin => "keypadbegin",
         KeyCode::Modifier(_) => "",
     };
 
-    let mut modifiers = Vec::with_capacity(3);
+    let mut modifiers = Vec::with_capacity(6);
 
     if key_event.modifiers.intersects(KeyModifiers::CONTROL) {
         modifiers.push("ctrl");
@@ -281,6 +303,18 @@ pub fn key_event_to_string(key_event: &KeyEvent) -> String {
         modifiers.push("alt");
     }
 
+    if key_event.modifiers.intersects(KeyModifiers::SUPER) {
+        modifiers.push("super");
+    }
+
+    if key_event.modifiers.intersects(KeyModifiers::HYPER) {
+        modifiers.push("hyper");
+    }
+
+    if key_event.modifiers.intersects(KeyModifiers::META) {
+        modifiers.push("meta");
+    }
+
     let mut key = modifiers.join("-");
 
     if !key.is_empty() {
@@ -583,6 +617,29 @@ mod tests {
         assert!(parse_key_event("ctrl-invalid-key").is_err());
     }
 
+    #[test]
+    fn test_enhanced_keys() {
+        assert_eq!(
+            parse_key_event("super-ctrl-i").unwrap(),
+            KeyEvent::new(
+                KeyCode::Char('i'),
+                KeyModifiers::SUPER | KeyModifiers::CONTROL
+            )
+        );
+        assert_eq!(
+            parse_key_event("f13").unwrap(),
+            KeyEvent::new(KeyCode::F(13), KeyModifiers::empty())
+        );
+        assert_eq!(
+            parse_key_event("meta-capslock").unwrap(),
+            KeyEvent::new(KeyCode::CapsLock, KeyModifiers::META)
+        );
+        assert!(parse_key_event("f36").is_err());
+
+        let key = parse_key_event("hyper-f13").unwrap();
+        assert_eq!(parse_key_event(&key_event_to_string(&key)).unwrap(), key);
+    }
+
     #[test]
     fn test_case_insensitivity() {
         assert_eq!(
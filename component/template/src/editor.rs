@@ -0,0 +1,83 @@
+use std::{env, fs, io::Write};
+
+use color_eyre::{eyre::eyre, Result};
+use tokio::process::Command;
+
+/// The editor to use when neither `$VISUAL` nor `$EDITOR` is set.
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+
+/// The user's editor: `$VISUAL`, `$EDITOR` or a platform default, in that order.
+pub fn command() -> String {
+    command_from(env::var("VISUAL").ok(), env::var("EDITOR").ok())
+}
+
+/// The first editor that is set and not empty, or the platform default.
+fn command_from(visual: Option<String>, editor: Option<String>) -> String {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+/// Edit `text` in a temporary file with `editor`, which may include arguments, e.g. `code --wait`,
+/// and return the result. This is usually the user's editor, see [`command`].
+///
+/// The terminal must be released by the tui first, see [`crate::tui::Tui::release`].
+///
+/// The file gets a random name and is only readable by the current user, as the text may be
+/// private. It is removed again when the editor is done.
+pub async fn edit_with(editor: &str, text: &str) -> Result<String> {
+    let mut file = tempfile::Builder::new()
+        .prefix(concat!(env!("CARGO_PKG_NAME"), "-"))
+        .suffix(".txt")
+        .tempfile()?;
+    file.write_all(text.as_bytes())?;
+    file.flush()?;
+    let mut args = editor.split_whitespace();
+    let program = args.next().ok_or_else(|| eyre!("no editor given"))?;
+    let status = Command::new(program)
+        .args(args)
+        .arg(file.path())
+        .status()
+        .await
+        .map_err(|err| eyre!("failed to run {program}: {err}"))?;
+    if !status.success() {
+        return Err(eyre!("{program} exited with {status}"));
+    }
+    // read by path, as editors often replace the file instead of writing to it
+    Ok(fs::read_to_string(file.path())?)
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_edit_returns_file_contents() -> Result<()> {
+        assert_eq!(
+            edit_with("true", "first line\nsecond").await?,
+            "first line\nsecond"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_command_skips_empty_variables() {
+        let set = |editor: &str| Some(editor.to_string());
+        assert_eq!(command_from(set("code --wait"), set("vim")), "code --wait");
+        assert_eq!(command_from(set(""), set("vim")), "vim");
+        assert_eq!(command_from(None, set(" ")), DEFAULT_EDITOR);
+    }
+
+    #[tokio::test]
+    async fn test_edit_fails_when_editor_fails() {
+        assert!(edit_with("false", "text").await.is_err());
+        assert!(edit_with("no-such-editor-here", "text").await.is_err());
+    }
+}
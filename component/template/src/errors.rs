@@ -1,7 +1,11 @@
-use std::env;
+use std::{env, fmt, time::SystemTime};
 
-use color_eyre::Result;
+use color_eyre::{Report, Result};
+use serde::{Deserialize, Serialize};
 use tracing::error;
+use tracing_error::SpanTraceStatus;
+
+use crate::action::Severity;
 
 pub fn init() -> Result<()> {
     let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default()
@@ -15,10 +19,8 @@ pub fn init() -> Result<()> {
         .into_hooks();
     eyre_hook.install()?;
     std::panic::set_hook(Box::new(move |panic_info| {
-        if let Ok(mut t) = crate::tui::Tui::new() {
-            if let Err(r) = t.exit() {
-                error!("Unable to exit Terminal: {:?}", r);
-            }
+        if let Err(r) = crate::tui::restore() {
+            error!("Unable to restore Terminal: {:?}", r);
         }
 
         #[cfg(not(debug_assertions))]
@@ -49,6 +51,83 @@ pub fn init() -> Result<()> {
     Ok(())
 }
 
+/// A serializable description of an error that is sent as [`Action::Error`].
+///
+/// Unlike a plain string, this keeps the cause chain and span trace of the original
+/// [`color_eyre::Report`], so the message shown in the UI and the one written to the log agree.
+///
+/// [`Action::Error`]: crate::action::Action::Error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorDetails {
+    pub severity: Severity,
+    /// The id of the component that the error originated from, if any.
+    pub component: Option<String>,
+    pub timestamp: SystemTime,
+    pub message: String,
+    /// The causes of the error, from the outermost to the root cause.
+    pub causes: Vec<String>,
+    pub span_trace: Option<String>,
+}
+
+impl ErrorDetails {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            component: None,
+            timestamp: SystemTime::now(),
+            message: message.into(),
+            causes: Vec::new(),
+            span_trace: None,
+        }
+    }
+
+    /// Capture the message, cause chain and span trace of a report.
+    pub fn from_report(report: &Report) -> Self {
+        let span_trace = report
+            .handler()
+            .downcast_ref::<color_eyre::Handler>()
+            .and_then(|handler| handler.span_trace())
+            .filter(|span_trace| span_trace.status() == SpanTraceStatus::CAPTURED)
+            .map(|span_trace| span_trace.to_string());
+        Self {
+            causes: report
+                .chain()
+                .skip(1)
+                .map(|cause| cause.to_string())
+                .collect(),
+            span_trace,
+            ..Self::new(report.to_string())
+        }
+    }
+
+    #[allow(dead_code)] // Remove this once you start using the code
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    pub fn component(mut self, component: impl Into<String>) -> Self {
+        self.component = Some(component.into());
+        self
+    }
+}
+
+impl fmt::Display for ErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(component) = &self.component {
+            write!(f, "{component}: ")?;
+        }
+        write!(f, "{}", self.message)?;
+        for (index, cause) in self.causes.iter().enumerate() {
+            write!(f, "\n  {index}: {cause}")?;
+        }
+        if let Some(span_trace) = &self.span_trace {
+            write!(f, "\n\nSpan trace:\n{span_trace}")?;
+        }
+        Ok(())
+    }
+}
+
 /// Similar to the `std::dbg!` macro, but generates `tracing` events rather
 /// than printing to stdout.
 ///
@@ -74,3 +153,33 @@ macro_rules! trace_dbg {
                 trace_dbg!(level: tracing::Level::DEBUG, $ex)
         };
 }
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::eyre;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_error_details_keep_cause_chain() {
+        let report = eyre!("file not found")
+            .wrap_err("failed to read config")
+            .wrap_err("failed to start");
+        let details = ErrorDetails::from_report(&report).component("Home");
+        assert_eq!(details.message, "failed to start");
+        assert_eq!(details.causes, ["failed to read config", "file not found"]);
+        assert_eq!(
+            details.to_string(),
+            "Home: failed to start\n  0: failed to read config\n  1: file not found"
+        );
+    }
+
+    #[test]
+    fn test_error_details_roundtrip() -> Result<()> {
+        let details = ErrorDetails::new("oops").severity(Severity::Warning);
+        let json = serde_json::to_string(&details)?;
+        assert_eq!(serde_json::from_str::<ErrorDetails>(&json)?, details);
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+/// Tracks which component has keyboard focus and the order that focus cycles through.
+///
+/// Components are identified by their [`Component::id`](crate::components::Component::id). The
+/// ring only stores ids, so it can be rebuilt whenever the component tree changes without losing
+/// track of the focused component.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FocusRing {
+    ids: Vec<String>,
+    focused: Option<String>,
+}
+
+impl FocusRing {
+    /// Replace the focusable components in the ring.
+    ///
+    /// Focus stays on the focused component if it is still focusable, otherwise it moves to the
+    /// first component in the ring.
+    pub fn set_ids(&mut self, ids: Vec<String>) {
+        self.ids = ids;
+        if !self
+            .focused
+            .as_ref()
+            .is_some_and(|id| self.ids.contains(id))
+        {
+            self.focused = self.ids.first().cloned();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn focused(&self) -> Option<&str> {
+        self.focused.as_deref()
+    }
+
+    pub fn is_focused(&self, id: &str) -> bool {
+        self.focused() == Some(id)
+    }
+
+    /// Move focus to the component with the given id, if it is in the ring.
+    pub fn focus(&mut self, id: &str) {
+        if self.ids.iter().any(|candidate| candidate == id) {
+            self.focused = Some(id.to_string());
+        }
+    }
+
+    /// Move focus to the next component in the ring, wrapping around at the end.
+    pub fn focus_next(&mut self) {
+        self.step(1);
+    }
+
+    /// Move focus to the previous component in the ring, wrapping around at the start.
+    pub fn focus_previous(&mut self) {
+        self.step(self.ids.len().saturating_sub(1));
+    }
+
+    fn step(&mut self, offset: usize) {
+        if self.ids.is_empty() {
+            return;
+        }
+        let index = self
+            .focused
+            .as_ref()
+            .and_then(|focused| self.ids.iter().position(|id| id == focused))
+            .map_or(0, |index| (index + offset) % self.ids.len());
+        self.focused = Some(self.ids[index].clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn ring(ids: &[&str]) -> FocusRing {
+        let mut ring = FocusRing::default();
+        ring.set_ids(ids.iter().map(|id| id.to_string()).collect());
+        ring
+    }
+
+    #[test]
+    fn test_focus_starts_on_first() {
+        assert_eq!(ring(&["a", "b"]).focused(), Some("a"));
+        assert_eq!(ring(&[]).focused(), None);
+    }
+
+    #[test]
+    fn test_focus_next_wraps() {
+        let mut ring = ring(&["a", "b", "c"]);
+        ring.focus_next();
+        assert_eq!(ring.focused(), Some("b"));
+        ring.focus_next();
+        ring.focus_next();
+        assert_eq!(ring.focused(), Some("a"));
+    }
+
+    #[test]
+    fn test_focus_previous_wraps() {
+        let mut ring = ring(&["a", "b", "c"]);
+        ring.focus_previous();
+        assert_eq!(ring.focused(), Some("c"));
+        ring.focus_previous();
+        assert_eq!(ring.focused(), Some("b"));
+    }
+
+    #[test]
+    fn test_focus_by_id() {
+        let mut ring = ring(&["a", "b", "c"]);
+        ring.focus("c");
+        assert_eq!(ring.focused(), Some("c"));
+        ring.focus("unknown");
+        assert_eq!(ring.focused(), Some("c"));
+    }
+
+    #[test]
+    fn test_set_ids_keeps_focus() {
+        let mut ring = ring(&["a", "b", "c"]);
+        ring.focus("b");
+        ring.set_ids(vec!["b".into(), "d".into()]);
+        assert_eq!(ring.focused(), Some("b"));
+        ring.set_ids(vec!["d".into()]);
+        assert_eq!(ring.focused(), Some("d"));
+    }
+}
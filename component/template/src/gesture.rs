@@ -0,0 +1,296 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Offset, Position, Rect};
+use serde::{Deserialize, Serialize};
+
+/// The longest time between two clicks that still counts as a double- or triple-click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A higher-level mouse interaction, synthesized from raw mouse events.
+///
+/// Positions are in the coordinates of the component that receives the gesture, like the raw
+/// mouse events that components receive. A drag can leave the component to the left or top, where
+/// its position stops at 0, so drags also report `delta`, the signed distance that the pointer
+/// moved from `origin`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Gesture {
+    /// A button was pressed `count` times in quick succession at the same position: 1 for a
+    /// single click, 2 for a double-click and 3 for a triple-click.
+    Click {
+        button: MouseButton,
+        count: u8,
+        position: Position,
+    },
+    /// The pointer moved with a button held down, starting a drag at `origin`.
+    DragStart {
+        button: MouseButton,
+        origin: Position,
+        position: Position,
+        delta: Offset,
+    },
+    /// The pointer moved during a drag.
+    DragMove {
+        button: MouseButton,
+        origin: Position,
+        position: Position,
+        delta: Offset,
+    },
+    /// The button was released at the end of a drag.
+    DragEnd {
+        button: MouseButton,
+        origin: Position,
+        position: Position,
+        delta: Offset,
+    },
+    /// The pointer moved onto the component.
+    HoverEnter { position: Position },
+    /// The pointer moved off the component.
+    HoverLeave,
+}
+
+impl Gesture {
+    /// Translate the gesture from screen coordinates to coordinates within `area`. Positions left
+    /// of or above the area become 0, the delta of a drag stays the same.
+    pub fn local(self, area: Rect) -> Self {
+        let local = |position: Position| {
+            Position::new(
+                position.x.saturating_sub(area.x),
+                position.y.saturating_sub(area.y),
+            )
+        };
+        match self {
+            Gesture::Click {
+                button,
+                count,
+                position,
+            } => Gesture::Click {
+                button,
+                count,
+                position: local(position),
+            },
+            Gesture::DragStart {
+                button,
+                origin,
+                position,
+                delta,
+            } => Gesture::DragStart {
+                button,
+                origin: local(origin),
+                position: local(position),
+                delta,
+            },
+            Gesture::DragMove {
+                button,
+                origin,
+                position,
+                delta,
+            } => Gesture::DragMove {
+                button,
+                origin: local(origin),
+                position: local(position),
+                delta,
+            },
+            Gesture::DragEnd {
+                button,
+                origin,
+                position,
+                delta,
+            } => Gesture::DragEnd {
+                button,
+                origin: local(origin),
+                position: local(position),
+                delta,
+            },
+            Gesture::HoverEnter { position } => Gesture::HoverEnter {
+                position: local(position),
+            },
+            Gesture::HoverLeave => Gesture::HoverLeave,
+        }
+    }
+}
+
+/// Turns raw mouse events into clicks and drags, in screen coordinates.
+///
+/// Hovering depends on which component is under the pointer, so the app tracks that itself.
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    last_click: Option<LastClick>,
+    pressed: Option<(MouseButton, Position)>,
+    dragging: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastClick {
+    at: Instant,
+    button: MouseButton,
+    position: Position,
+    count: u8,
+}
+
+impl GestureRecognizer {
+    pub fn handle(&mut self, mouse: MouseEvent, now: Instant) -> Option<Gesture> {
+        let position = Position::new(mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(button) => {
+                self.pressed = Some((button, position));
+                self.dragging = false;
+                let count = match self.last_click {
+                    Some(last)
+                        if last.button == button
+                            && last.position == position
+                            && now.duration_since(last.at) <= MULTI_CLICK_INTERVAL =>
+                    {
+                        // a fourth click starts counting again
+                        last.count % 3 + 1
+                    }
+                    _ => 1,
+                };
+                self.last_click = Some(LastClick {
+                    at: now,
+                    button,
+                    position,
+                    count,
+                });
+                Some(Gesture::Click {
+                    button,
+                    count,
+                    position,
+                })
+            }
+            MouseEventKind::Drag(_) => {
+                let (button, origin) = self.pressed?;
+                let delta = delta(origin, position);
+                if std::mem::replace(&mut self.dragging, true) {
+                    Some(Gesture::DragMove {
+                        button,
+                        origin,
+                        position,
+                        delta,
+                    })
+                } else {
+                    Some(Gesture::DragStart {
+                        button,
+                        origin,
+                        position,
+                        delta,
+                    })
+                }
+            }
+            MouseEventKind::Up(_) => {
+                let (button, origin) = self.pressed.take()?;
+                std::mem::take(&mut self.dragging).then_some(Gesture::DragEnd {
+                    button,
+                    origin,
+                    position,
+                    delta: delta(origin, position),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How far the pointer moved from `origin` to `position`.
+fn delta(origin: Position, position: Position) -> Offset {
+    Offset {
+        x: i32::from(position.x) - i32::from(origin.x),
+        y: i32::from(position.y) - i32::from(origin.y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::empty(),
+        }
+    }
+
+    #[test]
+    fn test_multi_click() {
+        let mut gestures = GestureRecognizer::default();
+        let start = Instant::now();
+        let down = mouse(MouseEventKind::Down(MouseButton::Left), 3, 4);
+        let counts: Vec<_> = [0, 100, 200, 300, 1000]
+            .into_iter()
+            .map(
+                |ms| match gestures.handle(down, start + Duration::from_millis(ms)) {
+                    Some(Gesture::Click { count, .. }) => count,
+                    gesture => panic!("expected a click, got {gesture:?}"),
+                },
+            )
+            .collect();
+        assert_eq!(counts, [1, 2, 3, 1, 1]);
+    }
+
+    #[test]
+    fn test_drag() {
+        let mut gestures = GestureRecognizer::default();
+        let now = Instant::now();
+        let button = MouseButton::Left;
+        let origin = Position::new(1, 1);
+        gestures.handle(mouse(MouseEventKind::Down(button), 1, 1), now);
+        assert_eq!(
+            gestures.handle(mouse(MouseEventKind::Drag(button), 2, 1), now),
+            Some(Gesture::DragStart {
+                button,
+                origin,
+                position: Position::new(2, 1),
+                delta: Offset { x: 1, y: 0 },
+            })
+        );
+        assert_eq!(
+            gestures.handle(mouse(MouseEventKind::Drag(button), 0, 2), now),
+            Some(Gesture::DragMove {
+                button,
+                origin,
+                position: Position::new(0, 2),
+                delta: Offset { x: -1, y: 1 },
+            })
+        );
+        assert_eq!(
+            gestures.handle(mouse(MouseEventKind::Up(button), 4, 2), now),
+            Some(Gesture::DragEnd {
+                button,
+                origin,
+                position: Position::new(4, 2),
+                delta: Offset { x: 3, y: 1 },
+            })
+        );
+        // a release without a drag is part of a click
+        gestures.handle(mouse(MouseEventKind::Down(button), 1, 1), now);
+        assert_eq!(
+            gestures.handle(mouse(MouseEventKind::Up(button), 1, 1), now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_local() {
+        let gesture = Gesture::DragMove {
+            button: MouseButton::Left,
+            origin: Position::new(12, 5),
+            position: Position::new(3, 7),
+            delta: Offset { x: -9, y: 2 },
+        };
+        // the delta still tells how far the pointer moved past the left edge
+        assert_eq!(
+            gesture.local(Rect::new(10, 5, 20, 5)),
+            Gesture::DragMove {
+                button: MouseButton::Left,
+                origin: Position::new(2, 0),
+                position: Position::new(0, 2),
+                delta: Offset { x: -9, y: 2 },
+            }
+        );
+    }
+}
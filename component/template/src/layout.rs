@@ -0,0 +1,151 @@
+#![allow(dead_code)] // Remove this once you start using the code
+
+use std::collections::HashMap;
+
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// A node in a declarative layout tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutNode {
+    /// A named area that components can be assigned to.
+    Slot(String),
+    /// An area that is split into child nodes along a direction.
+    Split {
+        direction: Direction,
+        children: Vec<(Constraint, LayoutNode)>,
+    },
+}
+
+impl LayoutNode {
+    pub fn slot(name: impl Into<String>) -> Self {
+        Self::Slot(name.into())
+    }
+
+    pub fn vertical(children: impl IntoIterator<Item = (Constraint, LayoutNode)>) -> Self {
+        Self::Split {
+            direction: Direction::Vertical,
+            children: children.into_iter().collect(),
+        }
+    }
+
+    pub fn horizontal(children: impl IntoIterator<Item = (Constraint, LayoutNode)>) -> Self {
+        Self::Split {
+            direction: Direction::Horizontal,
+            children: children.into_iter().collect(),
+        }
+    }
+
+    fn compute(&self, area: Rect, areas: &mut HashMap<String, Rect>) {
+        match self {
+            Self::Slot(name) => {
+                areas.insert(name.clone(), area);
+            }
+            Self::Split {
+                direction,
+                children,
+            } => {
+                let constraints = children.iter().map(|(constraint, _)| *constraint);
+                let rects = Layout::new(*direction, constraints).split(area);
+                for ((_, child), rect) in children.iter().zip(rects.iter()) {
+                    child.compute(*rect, areas);
+                }
+            }
+        }
+    }
+}
+
+/// Computes the areas of the slots in a layout tree and the components assigned to them.
+///
+/// Components are assigned to slots by their [`Component::id`](crate::components::Component::id).
+/// The areas are only recalculated when [`LayoutRegistry::resize`] is called, which the app does
+/// on startup and on every [`Action::Resize`](crate::action::Action::Resize).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayoutRegistry {
+    root: Option<LayoutNode>,
+    assignments: HashMap<String, String>,
+    areas: HashMap<String, Rect>,
+}
+
+impl LayoutRegistry {
+    pub fn new(root: LayoutNode) -> Self {
+        Self {
+            root: Some(root),
+            ..Self::default()
+        }
+    }
+
+    /// Assign the component with the given id to a slot.
+    pub fn assign(mut self, component_id: impl Into<String>, slot: impl Into<String>) -> Self {
+        self.assignments.insert(component_id.into(), slot.into());
+        self
+    }
+
+    /// Recalculate the area of every slot to fit within `area`.
+    pub fn resize(&mut self, area: Rect) {
+        self.areas.clear();
+        if let Some(root) = &self.root {
+            root.compute(area, &mut self.areas);
+        }
+    }
+
+    /// The area of a slot, if the slot exists.
+    pub fn slot_area(&self, slot: &str) -> Option<Rect> {
+        self.areas.get(slot).copied()
+    }
+
+    /// The area of the slot that a component is assigned to, if any.
+    pub fn area_of(&self, component_id: &str) -> Option<Rect> {
+        self.assignments
+            .get(component_id)
+            .and_then(|slot| self.slot_area(slot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn registry() -> LayoutRegistry {
+        LayoutRegistry::new(LayoutNode::vertical([
+            (Constraint::Length(1), LayoutNode::slot("header")),
+            (
+                Constraint::Fill(1),
+                LayoutNode::horizontal([
+                    (Constraint::Length(10), LayoutNode::slot("sidebar")),
+                    (Constraint::Fill(1), LayoutNode::slot("body")),
+                ]),
+            ),
+            (Constraint::Length(1), LayoutNode::slot("status")),
+        ]))
+        .assign("Home", "body")
+    }
+
+    #[test]
+    fn test_slot_areas() {
+        let mut layout = registry();
+        layout.resize(Rect::new(0, 0, 40, 10));
+        assert_eq!(layout.slot_area("header"), Some(Rect::new(0, 0, 40, 1)));
+        assert_eq!(layout.slot_area("sidebar"), Some(Rect::new(0, 1, 10, 8)));
+        assert_eq!(layout.slot_area("body"), Some(Rect::new(10, 1, 30, 8)));
+        assert_eq!(layout.slot_area("status"), Some(Rect::new(0, 9, 40, 1)));
+        assert_eq!(layout.slot_area("missing"), None);
+    }
+
+    #[test]
+    fn test_component_areas() {
+        let mut layout = registry();
+        layout.resize(Rect::new(0, 0, 40, 10));
+        assert_eq!(layout.area_of("Home"), Some(Rect::new(10, 1, 30, 8)));
+        assert_eq!(layout.area_of("FpsCounter"), None);
+    }
+
+    #[test]
+    fn test_resize_recalculates() {
+        let mut layout = registry();
+        layout.resize(Rect::new(0, 0, 40, 10));
+        layout.resize(Rect::new(0, 0, 20, 5));
+        assert_eq!(layout.area_of("Home"), Some(Rect::new(10, 1, 10, 3)));
+    }
+}
@@ -0,0 +1,90 @@
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action,
+    components::{popup::Popup, Component},
+};
+
+/// A built-in overlay that can be opened with [`Action::PushOverlay`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Overlay {
+    /// A message box that is closed with `Enter` or `Esc`.
+    Message { title: String, message: String },
+    /// A dialog that sends `on_confirm` when the user answers yes.
+    Confirm {
+        title: String,
+        message: String,
+        on_confirm: Box<Action>,
+    },
+}
+
+impl Overlay {
+    /// Create the component that renders this overlay.
+    pub fn into_layer(self) -> OverlayLayer {
+        let popup = match self {
+            Self::Message { title, message } => Popup::message(title, message),
+            Self::Confirm {
+                title,
+                message,
+                on_confirm,
+            } => Popup::confirm(title, message, *on_confirm),
+        };
+        let height = popup.height();
+        OverlayLayer::new(
+            Box::new(popup),
+            Constraint::Percentage(60),
+            Constraint::Length(height),
+        )
+    }
+}
+
+/// A component that is drawn above the base UI, centered in the frame.
+///
+/// While an overlay is open, the topmost one receives all key and mouse events.
+pub struct OverlayLayer {
+    pub component: Box<dyn Component>,
+    pub width: Constraint,
+    pub height: Constraint,
+}
+
+impl OverlayLayer {
+    pub fn new(component: Box<dyn Component>, width: Constraint, height: Constraint) -> Self {
+        Self {
+            component,
+            width,
+            height,
+        }
+    }
+
+    /// The area of the overlay, centered within `area`.
+    pub fn area(&self, area: Rect) -> Rect {
+        let [area] = Layout::horizontal([self.width])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([self.height])
+            .flex(Flex::Center)
+            .areas(area);
+        area
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_layer_area_is_centered() {
+        let layer = Overlay::Message {
+            title: "Title".into(),
+            message: "Message".into(),
+        }
+        .into_layer();
+        assert_eq!(
+            layer.area(Rect::new(0, 0, 100, 20)),
+            Rect::new(20, 8, 60, 5)
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, LineWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use color_eyre::{eyre::eyre, Result};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use ratatui::layout::Size;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    action::Action,
+    capabilities::Capabilities,
+    tui::{CrosstermEvents, Event, EventSource, Features},
+};
+
+/// A single line of a recorded session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Milliseconds since the recording started.
+    pub elapsed_ms: u64,
+    #[serde(flatten)]
+    pub record: Record,
+}
+
+/// What happened at a point in a recorded session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Record {
+    /// An event that the app received from the terminal.
+    Event(Event),
+    /// An action that the app dispatched to its components.
+    Action(Action),
+}
+
+/// Writes every incoming event and dispatched action to a JSON lines file.
+///
+/// Each entry is flushed as soon as it is written, so the recording is complete even if the app
+/// crashes.
+pub struct Recorder {
+    writer: LineWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            writer: LineWriter::new(File::create(path)?),
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record_event(&mut self, event: &Event) -> Result<()> {
+        self.write(Record::Event(event.clone()))
+    }
+
+    pub fn record_action(&mut self, action: &Action) -> Result<()> {
+        self.write(Record::Action(action.clone()))
+    }
+
+    fn write(&mut self, record: Record) -> Result<()> {
+        let entry = Entry {
+            elapsed_ms: self.start.elapsed().as_millis() as u64,
+            record,
+        };
+        serde_json::to_writer(&mut self.writer, &entry)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+/// An event source that plays the events of a recorded session with their original timing,
+/// instead of reading them from the terminal.
+///
+/// The terminal is still set up to draw on, but its input is never read, so nothing that is typed
+/// during the replay gets mixed into the session. The session is drawn at the size of the terminal
+/// that it was recorded in, see [`Replay::size`].
+///
+/// Recorded actions are skipped, as the app dispatches the same actions again when it handles the
+/// replayed events. So are the recorded [`Event::Init`], ticks and frames, which the event loop
+/// sends as usual. Once every event has been replayed, the app quits.
+pub struct Replay {
+    terminal: CrosstermEvents,
+    /// The events that haven't been replayed yet, each with the time since the one before it.
+    events: Arc<Mutex<VecDeque<(Duration, Event)>>>,
+    size: Option<Size>,
+    max_size: Size,
+}
+
+impl Replay {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        let mut events = VecDeque::new();
+        let mut size = None;
+        let mut max_size = Size::default();
+        let mut last = Duration::ZERO;
+        for line in file.lines() {
+            let entry: Entry = serde_json::from_str(&line?)?;
+            let event = match entry.record {
+                Record::Event(Event::Init | Event::Tick | Event::Render) | Record::Action(_) => {
+                    continue
+                }
+                Record::Event(event) => event,
+            };
+            if let Event::Resize(width, height) = event {
+                // the app records the size of the terminal before any other event
+                if events.is_empty() {
+                    size = Some(Size::new(width, height));
+                }
+                max_size.width = max_size.width.max(width);
+                max_size.height = max_size.height.max(height);
+            }
+            let elapsed = Duration::from_millis(entry.elapsed_ms);
+            events.push_back((elapsed.saturating_sub(last), event));
+            last = elapsed;
+        }
+        // the session is over, even if the recording stopped before the user quit
+        events.push_back((Duration::ZERO, Event::Quit));
+        Ok(Self {
+            terminal: CrosstermEvents::default(),
+            events: Arc::new(Mutex::new(events)),
+            size,
+            max_size,
+        })
+    }
+
+    /// The size of the terminal when the session was recorded, unless the recording doesn't say.
+    pub fn size(&self) -> Option<Size> {
+        self.size
+    }
+
+    /// Check that the terminal is at least as large as it was at any point of the recording, so
+    /// that every frame of the session fits.
+    pub fn check_fits(&self, terminal: Size) -> Result<()> {
+        let max = self.max_size;
+        if terminal.width < max.width || terminal.height < max.height {
+            return Err(eyre!(
+                "the session was recorded in a {}x{} terminal, which doesn't fit in this {}x{} one",
+                max.width,
+                max.height,
+                terminal.width,
+                terminal.height
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl EventSource for Replay {
+    fn enter(&mut self, _features: Features) -> Result<()> {
+        // the input is never read, so there is no point in asking the terminal for more of it
+        let features = Features {
+            alternate_screen: true,
+            mouse: false,
+            paste: false,
+            focus_change: false,
+            keyboard_enhancement: false,
+        };
+        self.terminal.enter(features)
+    }
+
+    fn exit(&mut self, features: Features) -> Result<()> {
+        discard_input();
+        self.terminal.exit(features)
+    }
+
+    fn events(&mut self) -> BoxStream<'static, Event> {
+        // after a suspend, carry on with the events that haven't been replayed yet
+        let events = self.events.clone();
+        stream::unfold(events, |events| async move {
+            let (delay, event) = events
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .front()
+                .cloned()?;
+            tokio::time::sleep(delay).await;
+            events
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pop_front();
+            Some((event, events))
+        })
+        .boxed()
+    }
+
+    fn capabilities(&mut self) -> Result<Capabilities> {
+        Ok(Capabilities::from_env())
+    }
+}
+
+/// Throw away whatever was typed during the replay, so that it doesn't end up in the shell.
+#[cfg(unix)]
+fn discard_input() {
+    // SAFETY: tcflush only takes a file descriptor and a constant
+    unsafe {
+        libc::tcflush(libc::STDIN_FILENO, libc::TCIFLUSH);
+    }
+}
+
+#[cfg(not(unix))]
+fn discard_input() {}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_replay_plays_recorded_input() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("session-{}.jsonl", std::process::id()));
+        let mut recorder = Recorder::create(&path)?;
+        recorder.record_event(&Event::Resize(80, 24))?;
+        recorder.record_event(&Event::Init)?;
+        recorder.record_event(&Event::Key(KeyEvent::from(KeyCode::Char('x'))))?;
+        recorder.record_action(&Action::Quit)?;
+        recorder.record_event(&Event::Tick)?;
+        recorder.record_event(&Event::Resize(100, 20))?;
+        drop(recorder);
+        let mut replay = Replay::open(&path)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(replay.size(), Some(Size::new(80, 24)));
+        assert!(replay.check_fits(Size::new(100, 24)).is_ok());
+        assert!(replay.check_fits(Size::new(80, 24)).is_err());
+
+        let events: Vec<String> = replay
+            .events()
+            .map(|event| format!("{event:?}"))
+            .collect()
+            .await;
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0], "Resize(80, 24)");
+        assert!(events[1].starts_with("Key("));
+        assert_eq!(events[2..], ["Resize(100, 20)", "Quit"]);
+        // nothing is replayed twice, e.g. after a suspend
+        assert_eq!(replay.events().count().await, 0);
+        Ok(())
+    }
+}
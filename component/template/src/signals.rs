@@ -0,0 +1,99 @@
+//! Turn the signals that would otherwise kill the app, or leave the terminal in a bad state, into
+//! events.
+//!
+//! | Signal              | Event             |
+//! |---------------------|-------------------|
+//! | `SIGTERM`, `SIGINT` | [`Event::Quit`]   |
+//! | `SIGHUP`            | [`Event::Reload`] |
+//! | `SIGCONT`           | [`Event::Resume`] |
+//!
+//! Once these signals are handled, they no longer terminate the process by themselves, so the app
+//! quits through its usual path and restores the terminal on the way out. If the app does not quit
+//! before a second `SIGTERM` or `SIGINT` arrives, the terminal is restored and the process exits
+//! immediately.
+
+use color_eyre::Result;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::tui::Event;
+
+/// Forwards signals to the event channel until it is dropped.
+#[derive(Debug, Default)]
+pub struct SignalListener {
+    #[cfg(not(windows))]
+    inner: Option<(signal_hook::iterator::Handle, std::thread::JoinHandle<()>)>,
+}
+
+/// Start forwarding signals as events to `event_tx`.
+///
+/// Signals are not supported on Windows, where this does nothing.
+#[cfg(not(windows))]
+pub fn listen(event_tx: UnboundedSender<Event>) -> Result<SignalListener> {
+    use signal_hook::{
+        consts::signal::{SIGCONT, SIGHUP, SIGINT, SIGTERM},
+        iterator::Signals,
+    };
+
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP, SIGCONT])?;
+    let handle = signals.handle();
+    let thread = std::thread::Builder::new()
+        .name("signals".into())
+        .spawn(move || {
+            let mut quitting = false;
+            for signal in signals.forever() {
+                let event = match signal {
+                    SIGTERM | SIGINT if quitting => {
+                        let _ = crate::tui::restore();
+                        std::process::exit(128 + signal);
+                    }
+                    SIGTERM | SIGINT => {
+                        quitting = true;
+                        Event::Quit
+                    }
+                    SIGHUP => Event::Reload,
+                    SIGCONT => Event::Resume,
+                    _ => continue,
+                };
+                if event_tx.send(event).is_err() {
+                    // the tui has gone away, so there is nobody left to tell
+                    break;
+                }
+            }
+        })?;
+    Ok(SignalListener {
+        inner: Some((handle, thread)),
+    })
+}
+
+#[cfg(windows)]
+pub fn listen(_event_tx: UnboundedSender<Event>) -> Result<SignalListener> {
+    Ok(SignalListener::default())
+}
+
+impl Drop for SignalListener {
+    fn drop(&mut self) {
+        #[cfg(not(windows))]
+        if let Some((handle, thread)) = self.inner.take() {
+            handle.close();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use signal_hook::{consts::signal::SIGCONT, low_level::raise};
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sigcont_resumes() -> Result<()> {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+        let _listener = listen(event_tx)?;
+        // continuing a process that is not stopped does nothing else
+        raise(SIGCONT)?;
+        assert!(matches!(event_rx.recv().await, Some(Event::Resume)));
+        Ok(())
+    }
+}
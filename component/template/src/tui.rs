@@ -1,8 +1,10 @@
 #![allow(dead_code)] // Remove this once you start using the code
 
 use std::{
-    io::{stdout, Stdout},
+    future::Future,
+    io::{stdout, Stdout, Write},
     ops::{Deref, DerefMut},
+    sync::{Mutex, PoisonError},
     time::Duration,
 };
 
@@ -10,22 +12,33 @@ use color_eyre::Result;
 use crossterm::{
     cursor,
     event::{
-        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
-        Event as CrosstermEvent, EventStream, KeyEvent, KeyEventKind, MouseEvent,
+        DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+        EnableFocusChange, EnableMouseCapture, Event as CrosstermEvent, EventStream, KeyEvent,
+        KeyboardEnhancementFlags, MouseEvent, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
     },
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
-use futures::{FutureExt, StreamExt};
-use ratatui::backend::CrosstermBackend as Backend;
+use futures::{stream::BoxStream, FutureExt, StreamExt};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Position, Rect},
+    Terminal, TerminalOptions, Viewport,
+};
 use serde::{Deserialize, Serialize};
 use tokio::{
-    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        watch,
+    },
     task::JoinHandle,
-    time::interval,
+    time::{interval, timeout},
 };
 use tokio_util::sync::CancellationToken;
 use tracing::error;
 
+use crate::{capabilities::Capabilities, clipboard};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Event {
     Init,
@@ -40,156 +53,440 @@ pub enum Event {
     Key(KeyEvent),
     Mouse(MouseEvent),
     Resize(u16, u16),
+    /// The app was asked to reload its configuration, e.g. with `SIGHUP`.
+    Reload,
+    /// The process was continued after being stopped, e.g. with `SIGCONT`, and anything that
+    /// the shell printed in the meantime needs to be drawn over.
+    Resume,
+}
+
+/// How long [`Tui::stop`] waits for the event loop to finish before aborting it.
+const STOP_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The optional terminal features that a [`Tui`] asks its [`EventSource`] to enable.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    pub mouse: bool,
+    pub paste: bool,
+    /// Whether the tui takes over the alternate screen. This is only the case for
+    /// [`Viewport::Fullscreen`]; inline and fixed viewports draw on the main screen.
+    pub alternate_screen: bool,
+    /// Whether the terminal reports when it gains and loses focus, as [`Event::FocusGained`] and
+    /// [`Event::FocusLost`].
+    pub focus_change: bool,
+    /// Whether to ask the terminal for the kitty keyboard protocol, if it supports it. This
+    /// distinguishes keys such as `Ctrl-i` and `Tab`, and reports key repeat and release events.
+    pub keyboard_enhancement: bool,
+}
+
+/// How often the event loop of a [`Tui`] sends [`Event::Tick`] and [`Event::Render`].
+///
+/// These can be changed while the event loop is running with [`Tui::set_rates`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rates {
+    /// Ticks per second.
+    pub tick_rate: f64,
+    /// The maximum number of frames per second.
+    pub frame_rate: f64,
+    /// Whether ticks are paused, e.g. while the app is in the background.
+    pub ticks_paused: bool,
+    /// Whether frames are paused, e.g. while the terminal is unfocused or nothing needs drawing.
+    pub frames_paused: bool,
+}
+
+impl Rates {
+    /// The lowest tick or frame rate, once every 100 seconds.
+    pub const MIN_RATE: f64 = 0.01;
+    /// The highest tick or frame rate.
+    pub const MAX_RATE: f64 = 1000.0;
+
+    /// Limit `rate` to the rates that the event loop can run at.
+    pub fn clamp(rate: f64) -> f64 {
+        if rate.is_nan() {
+            Self::MIN_RATE
+        } else {
+            rate.clamp(Self::MIN_RATE, Self::MAX_RATE)
+        }
+    }
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Self {
+            tick_rate: 4.0,
+            frame_rate: 60.0,
+            ticks_paused: false,
+            frames_paused: false,
+        }
+    }
 }
 
-pub struct Tui {
-    pub terminal: ratatui::Terminal<Backend<Stdout>>,
+/// Where a [`Tui`] reads its input events from.
+///
+/// The source also owns the terminal modes that are needed to read those events, such as raw mode
+/// and the alternate screen, so that backends other than crossterm (or no real terminal at all)
+/// can be plugged in without changing the rest of the tui.
+pub trait EventSource {
+    /// Prepare the terminal for the tui.
+    fn enter(&mut self, features: Features) -> Result<()>;
+
+    /// Restore the terminal to the state that it was in before [`EventSource::enter`].
+    fn exit(&mut self, features: Features) -> Result<()>;
+
+    /// A new stream of input events. This is called every time the event loop starts, including
+    /// after the tui is resumed.
+    fn events(&mut self) -> BoxStream<'static, Event>;
+
+    /// Find out what the terminal supports. This is called once, right after the first
+    /// [`EventSource::enter`].
+    fn capabilities(&mut self) -> Result<Capabilities> {
+        Ok(Capabilities::from_env())
+    }
+
+    /// Put `text` on the system clipboard, and return whether that is supported.
+    fn set_clipboard(&mut self, text: &str) -> Result<bool> {
+        let _ = text; // to appease clippy
+        Ok(false)
+    }
+
+    /// Stop the process, e.g. with `SIGTSTP`, after the terminal has been restored. This returns
+    /// once the process is continued.
+    fn suspend(&mut self) -> Result<()> {
+        #[cfg(not(windows))]
+        signal_hook::low_level::raise(signal_hook::consts::signal::SIGTSTP)?;
+        Ok(())
+    }
+}
+
+/// The features that [`CrosstermEvents`] has enabled, so that [`restore`] undoes exactly those
+/// even when it is called from somewhere that doesn't know how the tui was configured.
+static ENABLED: Mutex<Option<Features>> = Mutex::new(None);
+
+/// Restore the terminal from the state that [`CrosstermEvents`] put it in.
+///
+/// This does nothing if the terminal has already been restored, so it is safe to call from the
+/// panic hook. In particular it only leaves the alternate screen if it was entered, as leaving it
+/// otherwise moves the cursor of an inline tui.
+pub fn restore() -> Result<()> {
+    let Some(features) = ENABLED
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+    else {
+        return Ok(());
+    };
+    if features.keyboard_enhancement {
+        crossterm::execute!(stdout(), PopKeyboardEnhancementFlags)?;
+    }
+    if features.focus_change {
+        crossterm::execute!(stdout(), DisableFocusChange)?;
+    }
+    if features.paste {
+        crossterm::execute!(stdout(), DisableBracketedPaste)?;
+    }
+    if features.mouse {
+        crossterm::execute!(stdout(), DisableMouseCapture)?;
+    }
+    if features.alternate_screen {
+        crossterm::execute!(stdout(), LeaveAlternateScreen)?;
+    }
+    crossterm::execute!(stdout(), cursor::Show)?;
+    crossterm::terminal::disable_raw_mode()?;
+    Ok(())
+}
+
+/// Reads events from crossterm and sets up the terminal with crossterm commands.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CrosstermEvents {
+    /// Whether the terminal supports the kitty keyboard protocol, once it has been asked.
+    keyboard_enhancement: Option<bool>,
+    /// Keys that were typed while the terminal was queried, which come before any other event.
+    typed: Vec<KeyEvent>,
+}
+
+impl CrosstermEvents {
+    /// Ask the terminal whether it supports the kitty keyboard protocol. This can take a while
+    /// when the terminal doesn't answer, so it is only asked once.
+    fn supports_keyboard_enhancement(&mut self) -> Result<bool> {
+        if let Some(supported) = self.keyboard_enhancement {
+            return Ok(supported);
+        }
+        let supported = crossterm::terminal::supports_keyboard_enhancement()?;
+        self.keyboard_enhancement = Some(supported);
+        Ok(supported)
+    }
+}
+
+impl EventSource for CrosstermEvents {
+    fn enter(&mut self, features: Features) -> Result<()> {
+        crossterm::terminal::enable_raw_mode()?;
+        // track the features before anything else can fail, so that `restore` also leaves raw mode
+        // when entering fails halfway, which `Tui::enter` and the panic hook call it for, and only
+        // add the keyboard enhancement flags once they are pushed, as they are only popped then
+        let mut enabled = Features {
+            keyboard_enhancement: false,
+            ..features
+        };
+        *ENABLED.lock().unwrap_or_else(PoisonError::into_inner) = Some(enabled);
+        if features.alternate_screen {
+            crossterm::execute!(stdout(), EnterAlternateScreen)?;
+        }
+        crossterm::execute!(stdout(), cursor::Hide)?;
+        if features.mouse {
+            crossterm::execute!(stdout(), EnableMouseCapture)?;
+        }
+        if features.paste {
+            crossterm::execute!(stdout(), EnableBracketedPaste)?;
+        }
+        if features.focus_change {
+            crossterm::execute!(stdout(), EnableFocusChange)?;
+        }
+        if features.keyboard_enhancement && self.supports_keyboard_enhancement()? {
+            crossterm::execute!(
+                stdout(),
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                )
+            )?;
+            enabled.keyboard_enhancement = true;
+            *ENABLED.lock().unwrap_or_else(PoisonError::into_inner) = Some(enabled);
+        }
+        Ok(())
+    }
+
+    fn exit(&mut self, _features: Features) -> Result<()> {
+        // restore what was actually enabled, which is tracked for the panic hook anyway
+        restore()
+    }
+
+    fn capabilities(&mut self) -> Result<Capabilities> {
+        let mut capabilities = Capabilities::from_env();
+        // reuse the answer from entering, and otherwise keep the guess rather than asking now
+        if let Some(supported) = self.keyboard_enhancement {
+            capabilities.keyboard_enhancement = supported;
+        }
+        let (capabilities, typed) = capabilities.query();
+        self.typed.extend(typed);
+        Ok(capabilities)
+    }
+
+    fn set_clipboard(&mut self, text: &str) -> Result<bool> {
+        let mut stdout = stdout();
+        stdout.write_all(clipboard::osc52(text).as_bytes())?;
+        stdout.flush()?;
+        Ok(true)
+    }
+
+    fn events(&mut self) -> BoxStream<'static, Event> {
+        let typed = self.typed.drain(..).map(Event::Key).collect::<Vec<_>>();
+        let events = EventStream::new().map(|event| match event {
+            Ok(event) => match event {
+                CrosstermEvent::Key(key) => Event::Key(key),
+                CrosstermEvent::Mouse(mouse) => Event::Mouse(mouse),
+                CrosstermEvent::Resize(x, y) => Event::Resize(x, y),
+                CrosstermEvent::FocusLost => Event::FocusLost,
+                CrosstermEvent::FocusGained => Event::FocusGained,
+                CrosstermEvent::Paste(s) => Event::Paste(s),
+            },
+            Err(_) => Event::Error,
+        });
+        futures::stream::iter(typed).chain(events).boxed()
+    }
+}
+
+pub struct Tui<B: Backend = CrosstermBackend<Stdout>, S: EventSource = CrosstermEvents> {
+    pub terminal: Terminal<B>,
+    pub source: S,
     pub task: JoinHandle<()>,
     pub cancellation_token: CancellationToken,
     pub event_rx: UnboundedReceiver<Event>,
     pub event_tx: UnboundedSender<Event>,
-    pub frame_rate: f64,
-    pub tick_rate: f64,
-    pub mouse: bool,
-    pub paste: bool,
+    pub rates: watch::Sender<Rates>,
+    pub features: Features,
+    pub viewport: Viewport,
+    entered: bool,
+    capabilities: Option<Capabilities>,
 }
 
 impl Tui {
     pub fn new() -> Result<Self> {
+        Self::with_source(CrosstermEvents::default())
+    }
+}
+
+impl<S: EventSource> Tui<CrosstermBackend<Stdout>, S> {
+    /// Draw with crossterm, but read the input events from `source`, e.g. a
+    /// [`Replay`](crate::session::Replay).
+    pub fn with_source(source: S) -> Result<Self> {
+        Self::with_backend(CrosstermBackend::new(stdout()), source)
+    }
+
+    /// Draw in the given viewport instead of taking over the whole screen.
+    ///
+    /// [`Viewport::Inline`] draws below the shell prompt and leaves the last frame in the
+    /// scrollback when the tui exits, which suits short-lived prompts.
+    pub fn viewport(mut self, viewport: Viewport) -> Result<Self> {
+        self.terminal = Terminal::with_options(
+            CrosstermBackend::new(stdout()),
+            TerminalOptions {
+                viewport: viewport.clone(),
+            },
+        )?;
+        self.features.alternate_screen = viewport == Viewport::Fullscreen;
+        self.viewport = viewport;
+        Ok(self)
+    }
+}
+
+impl<B: Backend, S: EventSource> Tui<B, S> {
+    pub fn with_backend(backend: B, source: S) -> Result<Self> {
+        Self::with_options(backend, source, Viewport::Fullscreen)
+    }
+
+    pub fn with_options(backend: B, source: S, viewport: Viewport) -> Result<Self> {
         let (event_tx, event_rx) = mpsc::unbounded_channel();
         Ok(Self {
-            terminal: ratatui::Terminal::new(Backend::new(stdout()))?,
+            terminal: Terminal::with_options(
+                backend,
+                TerminalOptions {
+                    viewport: viewport.clone(),
+                },
+            )?,
+            source,
             task: tokio::spawn(async {}),
             cancellation_token: CancellationToken::new(),
             event_rx,
             event_tx,
-            frame_rate: 60.0,
-            tick_rate: 4.0,
-            mouse: false,
-            paste: false,
+            rates: watch::Sender::new(Rates::default()),
+            features: Features {
+                alternate_screen: viewport == Viewport::Fullscreen,
+                focus_change: true,
+                ..Features::default()
+            },
+            viewport,
+            entered: false,
+            capabilities: None,
         })
     }
 
-    pub fn tick_rate(mut self, tick_rate: f64) -> Self {
-        self.tick_rate = tick_rate;
+    pub fn tick_rate(self, tick_rate: f64) -> Self {
+        self.rates
+            .send_modify(|rates| rates.tick_rate = Rates::clamp(tick_rate));
         self
     }
 
-    pub fn frame_rate(mut self, frame_rate: f64) -> Self {
-        self.frame_rate = frame_rate;
+    pub fn frame_rate(self, frame_rate: f64) -> Self {
+        self.rates
+            .send_modify(|rates| rates.frame_rate = Rates::clamp(frame_rate));
         self
     }
 
+    pub fn rates(&self) -> Rates {
+        *self.rates.borrow()
+    }
+
+    /// Change the tick and frame rates of the running event loop, without restarting it.
+    pub fn set_rates(&self, mut rates: Rates) {
+        rates.tick_rate = Rates::clamp(rates.tick_rate);
+        rates.frame_rate = Rates::clamp(rates.frame_rate);
+        self.rates.send_if_modified(|current| {
+            let changed = *current != rates;
+            *current = rates;
+            changed
+        });
+    }
+
     pub fn mouse(mut self, mouse: bool) -> Self {
-        self.mouse = mouse;
+        self.features.mouse = mouse;
         self
     }
 
     pub fn paste(mut self, paste: bool) -> Self {
-        self.paste = paste;
+        self.features.paste = paste;
+        self
+    }
+
+    pub fn focus_change(mut self, focus_change: bool) -> Self {
+        self.features.focus_change = focus_change;
+        self
+    }
+
+    pub fn keyboard_enhancement(mut self, keyboard_enhancement: bool) -> Self {
+        self.features.keyboard_enhancement = keyboard_enhancement;
         self
     }
 
     pub fn start(&mut self) {
         self.cancel(); // Cancel any existing task
         self.cancellation_token = CancellationToken::new();
-        let event_loop = Self::event_loop(
+        let event_loop = event_loop(
+            self.source.events(),
             self.event_tx.clone(),
             self.cancellation_token.clone(),
-            self.tick_rate,
-            self.frame_rate,
+            self.rates.subscribe(),
         );
         self.task = tokio::spawn(async {
             event_loop.await;
         });
     }
 
-    async fn event_loop(
-        event_tx: UnboundedSender<Event>,
-        cancellation_token: CancellationToken,
-        tick_rate: f64,
-        frame_rate: f64,
-    ) {
-        let mut event_stream = EventStream::new();
-        let mut tick_interval = interval(Duration::from_secs_f64(1.0 / tick_rate));
-        let mut render_interval = interval(Duration::from_secs_f64(1.0 / frame_rate));
-
-        // if this fails, then it's likely a bug in the calling code
-        event_tx
-            .send(Event::Init)
-            .expect("failed to send init event");
-        loop {
-            let event = tokio::select! {
-                _ = cancellation_token.cancelled() => {
-                    break;
-                }
-                _ = tick_interval.tick() => Event::Tick,
-                _ = render_interval.tick() => Event::Render,
-                crossterm_event = event_stream.next().fuse() => match crossterm_event {
-                    Some(Ok(event)) => match event {
-                        CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => Event::Key(key),
-                        CrosstermEvent::Mouse(mouse) => Event::Mouse(mouse),
-                        CrosstermEvent::Resize(x, y) => Event::Resize(x, y),
-                        CrosstermEvent::FocusLost => Event::FocusLost,
-                        CrosstermEvent::FocusGained => Event::FocusGained,
-                        CrosstermEvent::Paste(s) => Event::Paste(s),
-                        _ => continue, // ignore other events
-                    }
-                    Some(Err(_)) => Event::Error,
-                    None => break, // the event stream has stopped and will not produce any more events
-                },
-            };
-            if event_tx.send(event).is_err() {
-                // the receiver has been dropped, so there's no point in continuing the loop
-                break;
-            }
-        }
-        cancellation_token.cancel();
-    }
-
-    pub fn stop(&self) -> Result<()> {
+    /// Stop the event loop, waiting for it to finish for a moment before aborting it.
+    pub async fn stop(&mut self) -> Result<()> {
         self.cancel();
-        let mut counter = 0;
-        while !self.task.is_finished() {
-            std::thread::sleep(Duration::from_millis(1));
-            counter += 1;
-            if counter > 50 {
-                self.task.abort();
-            }
-            if counter > 100 {
-                error!("Failed to abort task in 100 milliseconds for unknown reason");
-                break;
-            }
+        // a finished task must not be awaited again
+        if !self.task.is_finished() && timeout(STOP_TIMEOUT, &mut self.task).await.is_err() {
+            error!("Failed to stop the event loop in {STOP_TIMEOUT:?}, aborting it");
+            self.task.abort();
         }
         Ok(())
     }
 
     pub fn enter(&mut self) -> Result<()> {
-        crossterm::terminal::enable_raw_mode()?;
-        crossterm::execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
-        if self.mouse {
-            crossterm::execute!(stdout(), EnableMouseCapture)?;
+        // the source may have changed the terminal before failing, e.g. turned on raw mode, so that
+        // is undone straight away instead of leaving the shell in that state
+        self.entered = true;
+        if let Err(err) = self.source.enter(self.features) {
+            if let Err(restore_err) = self.restore(false) {
+                error!("Failed to restore the terminal: {restore_err:?}");
+            }
+            return Err(err);
         }
-        if self.paste {
-            crossterm::execute!(stdout(), EnableBracketedPaste)?;
+        if self.capabilities.is_none() {
+            // ask before the event loop starts reading the terminal's answers as input
+            self.capabilities = Some(self.source.capabilities()?);
         }
         self.start();
         Ok(())
     }
 
-    pub fn exit(&mut self) -> Result<()> {
-        self.stop()?;
-        if crossterm::terminal::is_raw_mode_enabled()? {
-            self.flush()?;
-            if self.paste {
-                crossterm::execute!(stdout(), DisableBracketedPaste)?;
-            }
-            if self.mouse {
-                crossterm::execute!(stdout(), DisableMouseCapture)?;
+    pub async fn exit(&mut self) -> Result<()> {
+        self.stop().await?;
+        self.restore(true)
+    }
+
+    /// Restore the terminal, unless it has already been restored since the tui was entered.
+    ///
+    /// Inline and fixed viewports either keep their last frame on screen and continue on the line
+    /// below it, or clear it so that the shell can reuse those lines while the tui is suspended.
+    fn restore(&mut self, keep_frame: bool) -> Result<()> {
+        // only try once, even if restoring fails halfway, so that it isn't repeated when dropped
+        if !std::mem::replace(&mut self.entered, false) {
+            return Ok(());
+        }
+        if self.viewport != Viewport::Fullscreen {
+            let area = self.terminal.get_frame().area();
+            if keep_frame {
+                self.terminal
+                    .set_cursor_position((0, area.bottom().saturating_sub(1)))?;
+                self.terminal.backend_mut().append_lines(1)?;
+            } else {
+                self.terminal.set_cursor_position(area.as_position())?;
+                self.terminal.clear()?;
             }
-            crossterm::execute!(stdout(), LeaveAlternateScreen, cursor::Show)?;
-            crossterm::terminal::disable_raw_mode()?;
         }
+        self.terminal.backend_mut().flush()?;
+        self.source.exit(self.features)?;
         Ok(())
     }
 
@@ -197,39 +494,267 @@ impl Tui {
         self.cancellation_token.cancel();
     }
 
-    pub fn suspend(&mut self) -> Result<()> {
-        self.exit()?;
-        #[cfg(not(windows))]
-        signal_hook::low_level::raise(signal_hook::consts::signal::SIGTSTP)?;
-        Ok(())
+    pub async fn suspend(&mut self) -> Result<()> {
+        self.stop().await?;
+        self.restore(false)?;
+        self.source.suspend()
     }
 
     pub fn resume(&mut self) -> Result<()> {
         self.enter()?;
+        if let Viewport::Inline(_) = self.viewport {
+            // the shell may have printed while the tui was suspended, so start again at the cursor
+            let size = self.terminal.size()?;
+            self.terminal.resize(Rect::from((Position::ORIGIN, size)))?;
+        }
         Ok(())
     }
 
+    /// Copy `text` to the system clipboard with an OSC 52 escape sequence, which also works over
+    /// SSH and in tmux. Returns false if the terminal doesn't support it.
+    pub fn copy(&mut self, text: &str) -> Result<bool> {
+        if !self.capabilities().clipboard {
+            return Ok(false);
+        }
+        self.source.set_clipboard(text)
+    }
+
+    /// What the terminal supports, which is detected when the tui is first entered.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities.unwrap_or_default()
+    }
+
+    /// Hand the terminal over to another program, e.g. an editor, while `task` runs, and take it
+    /// back afterwards.
+    ///
+    /// The program gets the terminal in its normal mode and on the main screen. Once it is done,
+    /// the tui is resumed and the whole frame is drawn again on the next draw.
+    pub async fn release<T>(&mut self, task: impl Future<Output = T>) -> Result<T> {
+        self.stop().await?;
+        self.restore(false)?;
+        let output = task.await;
+        self.resume()?;
+        self.terminal.clear()?;
+        Ok(output)
+    }
+
     pub async fn next_event(&mut self) -> Option<Event> {
         self.event_rx.recv().await
     }
 }
 
-impl Deref for Tui {
-    type Target = ratatui::Terminal<Backend<Stdout>>;
+impl<B: Backend, S: EventSource> Deref for Tui<B, S> {
+    type Target = Terminal<B>;
 
     fn deref(&self) -> &Self::Target {
         &self.terminal
     }
 }
 
-impl DerefMut for Tui {
+impl<B: Backend, S: EventSource> DerefMut for Tui<B, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.terminal
     }
 }
 
-impl Drop for Tui {
+impl<B: Backend, S: EventSource> Drop for Tui<B, S> {
+    /// Restore the terminal if the tui was not exited, e.g. because an error was returned early.
+    ///
+    /// This may run while a panic unwinds, so it only logs errors instead of panicking again.
     fn drop(&mut self) {
-        self.exit().unwrap();
+        self.cancel();
+        self.task.abort();
+        if let Err(err) = self.restore(true) {
+            error!("Failed to restore the terminal: {err:?}");
+        }
+    }
+}
+
+/// The time between two ticks or frames at `rate`, which is never zero or too long to represent.
+fn period(rate: f64) -> Duration {
+    Duration::from_secs_f64(1.0 / Rates::clamp(rate))
+}
+
+async fn event_loop(
+    mut event_stream: BoxStream<'static, Event>,
+    event_tx: UnboundedSender<Event>,
+    cancellation_token: CancellationToken,
+    mut rates: watch::Receiver<Rates>,
+) {
+    let mut current = *rates.borrow_and_update();
+    let mut tick_interval = interval(period(current.tick_rate));
+    let mut render_interval = interval(period(current.frame_rate));
+
+    // if this fails, then it's likely a bug in the calling code
+    event_tx
+        .send(Event::Init)
+        .expect("failed to send init event");
+    loop {
+        let event = tokio::select! {
+            _ = cancellation_token.cancelled() => {
+                break;
+            }
+            Ok(()) = rates.changed() => {
+                let next = *rates.borrow_and_update();
+                if next.tick_rate != current.tick_rate {
+                    tick_interval = interval(period(next.tick_rate));
+                } else if current.ticks_paused && !next.ticks_paused {
+                    // don't send the ticks that were missed while paused all at once
+                    tick_interval.reset();
+                }
+                if next.frame_rate != current.frame_rate {
+                    render_interval = interval(period(next.frame_rate));
+                } else if current.frames_paused && !next.frames_paused {
+                    render_interval.reset();
+                }
+                current = next;
+                continue;
+            }
+            _ = tick_interval.tick(), if !current.ticks_paused => Event::Tick,
+            _ = render_interval.tick(), if !current.frames_paused => Event::Render,
+            event = event_stream.next().fuse() => match event {
+                Some(event) => event,
+                None => break, // the event stream has stopped and will not produce any more events
+            },
+        };
+        if event_tx.send(event).is_err() {
+            // the receiver has been dropped, so there's no point in continuing the loop
+            break;
+        }
+    }
+    cancellation_token.cancel();
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::eyre;
+    use pretty_assertions::assert_eq;
+    use ratatui::backend::TestBackend;
+
+    use super::*;
+    use crate::app::harness::Scripted;
+
+    /// An event source that fails to enter, and counts how often it is asked to exit.
+    struct Failing(usize);
+
+    impl EventSource for Failing {
+        fn enter(&mut self, _features: Features) -> Result<()> {
+            Err(eyre!("the terminal went away"))
+        }
+
+        fn exit(&mut self, _features: Features) -> Result<()> {
+            self.0 += 1;
+            Ok(())
+        }
+
+        fn events(&mut self) -> BoxStream<'static, Event> {
+            futures::stream::pending().boxed()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_enter_restores_terminal() -> Result<()> {
+        let mut tui = Tui::with_backend(TestBackend::new(10, 2), Failing(0))?;
+        assert!(tui.enter().is_err());
+        assert_eq!(tui.source.0, 1);
+        // only once, not again when exiting or dropping the tui
+        tui.exit().await?;
+        assert_eq!(tui.source.0, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tui_with_test_backend() -> Result<()> {
+        let source = Scripted::new(vec![Event::Paste("hello".into())]);
+        let mut tui = Tui::with_backend(TestBackend::new(10, 2), source)?
+            .tick_rate(1.0)
+            .frame_rate(1.0);
+        tui.enter()?;
+        assert!(matches!(tui.next_event().await, Some(Event::Init)));
+        let mut pasted = None;
+        while let Some(event) = tui.next_event().await {
+            if let Event::Paste(text) = event {
+                pasted = Some(text);
+                break;
+            }
+        }
+        assert_eq!(pasted.as_deref(), Some("hello"));
+        tui.exit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_inline_exit_keeps_last_frame() -> Result<()> {
+        let mut backend = TestBackend::new(10, 6);
+        backend.set_cursor_position((0, 1))?;
+        let mut tui = Tui::with_options(backend, Scripted::default(), Viewport::Inline(2))?;
+        assert_eq!(tui.get_frame().area(), Rect::new(0, 1, 10, 2));
+        tui.enter()?;
+        tui.draw(|frame| frame.render_widget("prompt", frame.area()))?;
+        tui.exit().await?;
+        tui.backend().assert_buffer_lines([
+            "          ",
+            "prompt    ",
+            "          ",
+            "          ",
+            "          ",
+            "          ",
+        ]);
+        // the shell continues on the line below the frame
+        assert_eq!(tui.backend_mut().get_cursor_position()?.y, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pause_ticks() -> Result<()> {
+        let mut tui = Tui::with_backend(TestBackend::new(10, 2), Scripted::default())?
+            .tick_rate(1000.0)
+            .frame_rate(0.1);
+        tui.enter()?;
+        let mut rates = tui.rates();
+        rates.ticks_paused = true;
+        tui.set_rates(rates);
+
+        // drain the ticks that were sent before the event loop saw the change
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        while tui.event_rx.try_recv().is_ok() {}
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(tui.event_rx.try_recv().is_err());
+
+        rates.ticks_paused = false;
+        tui.set_rates(rates);
+        assert!(matches!(tui.next_event().await, Some(Event::Tick)));
+        tui.exit().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_extreme_rates_are_clamped() -> Result<()> {
+        let mut tui = Tui::with_backend(TestBackend::new(10, 2), Scripted::default())?
+            .tick_rate(1e-300)
+            .frame_rate(1e10);
+        assert_eq!(tui.rates().tick_rate, Rates::MIN_RATE);
+        assert_eq!(tui.rates().frame_rate, Rates::MAX_RATE);
+        tui.enter()?;
+        assert!(matches!(tui.next_event().await, Some(Event::Init)));
+
+        let mut rates = tui.rates();
+        rates.tick_rate = f64::MAX;
+        rates.frame_rate = f64::MIN_POSITIVE;
+        tui.set_rates(rates);
+        assert_eq!(tui.rates().tick_rate, Rates::MAX_RATE);
+        // the first tick of an interval is immediate, so wait for more ticks than the slowest rate
+        // would send
+        let ticks = async {
+            let mut ticks = 0;
+            while ticks < 3 {
+                if let Some(Event::Tick) = tui.next_event().await {
+                    ticks += 1;
+                }
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), ticks).await?;
+        tui.exit().await?;
+        Ok(())
     }
 }